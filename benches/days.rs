@@ -0,0 +1,139 @@
+use advent_of_code_2022::coord::{Coord2, Coord3};
+use advent_of_code_2022::grid::Grid;
+use advent_of_code_2022::{day12, day18, day20, day23, day8};
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::HashSet;
+use std::fs;
+
+/// Benchmark every solved day using its real input. Use `cargo bench day<num>` to only run a
+/// single day
+fn bench_days(c: &mut Criterion) {
+    let mut group = c.benchmark_group("days");
+
+    // Some days take seconds to solve, so we don't run more samples than we have to
+    group.sample_size(10);
+    for day in advent_of_code_2022::DAYS {
+        let input = fs::read_to_string(format!("data/day{}.txt", day)).unwrap();
+        group.bench_function(format!("day{}", day), |b| {
+            b.iter(|| advent_of_code_2022::run(day, &input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+/// Compare breadth first search and A* for day 12 on a large heightmap that slopes upwards
+/// towards the end
+fn bench_day12_large_heightmap(c: &mut Criterion) {
+    let size = 300;
+    let heightmap = (0..size)
+        .flat_map(|x| (0..size).map(move |y| Coord2::new(x, y)))
+        .map(|c| {
+            let height = ((c.x + c.y) * 26 / (2 * size)).min(25) as u8;
+            (c, height.saturating_sub(((c.x * c.y) % 7 == 0) as u8))
+        })
+        .collect::<Grid<u8>>();
+    let start = Coord2::new(0, 0);
+    let end = Coord2::new(size - 1, size - 1);
+    let bfs = day12::find_shortest_path_len_stats(&heightmap, &[start], &[end]);
+    let astar = day12::find_shortest_path_astar_stats(&heightmap, start, end);
+    assert!(astar.num_steps.is_some());
+    println!(
+        "day12_large_heightmap: bfs expanded {} positions, astar expanded {}",
+        bfs.num_expanded, astar.num_expanded
+    );
+
+    let mut group = c.benchmark_group("day12_large_heightmap");
+    group.bench_function("bfs", |b| {
+        b.iter(|| day12::find_shortest_path_len(&heightmap, &[start], &[end]))
+    });
+    group.bench_function("astar", |b| {
+        b.iter(|| day12::find_shortest_path_astar(&heightmap, start, end))
+    });
+    group.finish();
+}
+
+/// Compare the dense and the hash map backed grid for the scenic scores of day 8 on a 1000x1000
+/// forest of pseudo random trees
+fn bench_day8_large_grid(c: &mut Criterion) {
+    let mut seed: u64 = 2022;
+    let input = (0..1000)
+        .map(|_| {
+            (0..1000)
+                .map(|_| {
+                    seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                    char::from(b'0' + ((seed >> 33) % 10) as u8)
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let dense = day8::parse_grid(&input).unwrap();
+    let sparse = dense
+        .iter()
+        .map(|(pos, &h)| (pos, h))
+        .collect::<Grid<u32>>();
+
+    let mut group = c.benchmark_group("day8_large_grid");
+    group.sample_size(10);
+    group.bench_function("dense", |b| b.iter(|| day8::best_scenic(&dense)));
+    group.bench_function("hashmap", |b| b.iter(|| day8::best_scenic(&sparse)));
+    group.finish();
+}
+
+/// Benchmark the air pocket search of day 18 on a droplet that is much larger than the real one
+fn bench_day18_large_droplet(c: &mut Criterion) {
+    let cubes = (-40..=40)
+        .flat_map(|x| (-40..=40).flat_map(move |y| (-40..=40).map(move |z| (x, y, z))))
+        .filter(|&(x, y, z)| (x * x + y * y + z * z) % 7 != 0)
+        .map(|(x, y, z)| Coord3::new(x, y, z))
+        .collect::<HashSet<_>>();
+    c.bench_function("day18_large_droplet", |b| {
+        b.iter(|| day18::trapped_air(&cubes))
+    });
+}
+
+/// Compare the block based and the naive mixing of day 20 on a pseudo random file of 5000 values,
+/// which is about the size of the real input
+fn bench_day20_large_file(c: &mut Criterion) {
+    let mut seed: u64 = 2022;
+    let mut encrypted_file = (0..5000)
+        .map(|_| {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            ((seed >> 33) % 20_000) as isize - 10_000
+        })
+        .map(|v| if v == 0 { 1 } else { v })
+        .collect::<Vec<_>>();
+    encrypted_file[1234] = 0;
+
+    let mut group = c.benchmark_group("day20_large_file");
+    group.sample_size(10);
+    group.bench_function("blocks", |b| {
+        b.iter(|| day20::decrypt(&encrypted_file, 1, 1))
+    });
+    group.bench_function("naive", |b| {
+        b.iter(|| day20::decrypt_naive(&encrypted_file, 1, 1))
+    });
+    group.finish();
+}
+
+/// Benchmark day 23 on a generated grove that takes hundreds of rounds to settle
+fn bench_day23_large_grove(c: &mut Criterion) {
+    let elves = (0..40)
+        .flat_map(|x| (0..40).map(move |y| Coord2::new(x, y)))
+        .filter(|c| (c.x * 7 + c.y * 13) % 5 < 2)
+        .collect::<HashSet<_>>();
+    c.bench_function("day23_large_grove", |b| {
+        b.iter(|| day23::simulate(elves.clone(), usize::MAX))
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_days,
+    bench_day8_large_grid,
+    bench_day12_large_heightmap,
+    bench_day18_large_droplet,
+    bench_day20_large_file,
+    bench_day23_large_grove
+);
+criterion_main!(benches);
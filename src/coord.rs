@@ -0,0 +1,294 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
+
+/// Position on a 2D grid where y grows downwards
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coord2 {
+    pub x: isize,
+    pub y: isize,
+}
+
+/// Position in 3D space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coord3 {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
+}
+
+/// Coordinates that can be combined component by component
+pub trait Components: Copy {
+    /// Return the smallest value of each component
+    fn min_components(self, other: Self) -> Self;
+
+    /// Return the largest value of each component
+    fn max_components(self, other: Self) -> Self;
+}
+
+/// Return the smallest and the largest corner of the box that contains all the coordinates, or
+/// None if there are no coordinates
+pub fn bounding_box<C, I>(coords: I) -> Option<(C, C)>
+where
+    C: Components,
+    I: IntoIterator<Item = C>,
+{
+    let mut coords = coords.into_iter();
+    let first = coords.next()?;
+    Some(coords.fold((first, first), |(min, max), c| {
+        (min.min_components(c), max.max_components(c))
+    }))
+}
+
+/// Orthogonal direction on a 2D grid where y grows downwards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    /// All directions in clockwise order, starting with up
+    pub const ALL: [Self; 4] = [Self::Up, Self::Right, Self::Down, Self::Left];
+
+    /// Return the change in position when taking a single step in this direction
+    pub const fn delta(self) -> Coord2 {
+        match self {
+            Self::Up => Coord2::new(0, -1),
+            Self::Right => Coord2::new(1, 0),
+            Self::Down => Coord2::new(0, 1),
+            Self::Left => Coord2::new(-1, 0),
+        }
+    }
+}
+
+impl Coord2 {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan_distance(self, other: Self) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// Return the four orthogonally adjacent positions in the order up, right, down and left
+    pub fn neighbors4(self) -> impl Iterator<Item = Self> {
+        Direction::ALL.into_iter().map(move |d| self + d.delta())
+    }
+
+    /// Return the eight surrounding positions, including diagonals, clockwise from the top left
+    pub fn neighbors8(self) -> impl Iterator<Item = Self> {
+        [
+            Self::new(-1, -1),
+            Self::new(0, -1),
+            Self::new(1, -1),
+            Self::new(1, 0),
+            Self::new(1, 1),
+            Self::new(0, 1),
+            Self::new(-1, 1),
+            Self::new(-1, 0),
+        ]
+        .into_iter()
+        .map(move |delta| self + delta)
+    }
+}
+
+impl Coord3 {
+    pub const fn new(x: isize, y: isize, z: isize) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Return the six positions that share a face with this one
+    pub fn neighbors6(self) -> impl Iterator<Item = Self> {
+        [
+            Self::new(self.x - 1, self.y, self.z),
+            Self::new(self.x + 1, self.y, self.z),
+            Self::new(self.x, self.y - 1, self.z),
+            Self::new(self.x, self.y + 1, self.z),
+            Self::new(self.x, self.y, self.z - 1),
+            Self::new(self.x, self.y, self.z + 1),
+        ]
+        .into_iter()
+    }
+}
+
+impl Components for Coord2 {
+    fn min_components(self, other: Self) -> Self {
+        Self::new(self.x.min(other.x), self.y.min(other.y))
+    }
+
+    fn max_components(self, other: Self) -> Self {
+        Self::new(self.x.max(other.x), self.y.max(other.y))
+    }
+}
+
+impl Components for Coord3 {
+    fn min_components(self, other: Self) -> Self {
+        Self::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    fn max_components(self, other: Self) -> Self {
+        Self::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
+    }
+}
+
+impl Add for Coord2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Sub for Coord2 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// Parse a coordinate on the form `x,y`
+impl FromStr for Coord2 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((x_str, y_str)) = s.split_once(',') else {
+            return Err(anyhow!("Malformed coordinate {:?}", s));
+        };
+        Ok(Self::new(x_str.parse()?, y_str.parse()?))
+    }
+}
+
+/// Parse a coordinate on the form `x,y,z`
+impl FromStr for Coord3 {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((x_str, rest)) = s.split_once(',') else {
+            return Err(anyhow!("Malformed coordinate {:?}", s));
+        };
+        let Some((y_str, z_str)) = rest.split_once(',') else {
+            return Err(anyhow!("Malformed coordinate {:?}", s));
+        };
+        Ok(Self::new(x_str.parse()?, y_str.parse()?, z_str.parse()?))
+    }
+}
+
+/// Format the coordinate on the same form as it's parsed, like `1,2,3`
+impl fmt::Display for Coord3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manhattan_distance() {
+        assert_eq!(Coord2::new(8, 7).manhattan_distance(Coord2::new(2, 10)), 9);
+        assert_eq!(Coord2::new(-3, 0).manhattan_distance(Coord2::new(3, 0)), 6);
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        assert_eq!(bounding_box(Vec::<Coord2>::new()), None);
+        let point = Coord2::new(3, -1);
+        assert_eq!(bounding_box([point]), Some((point, point)));
+        assert_eq!(
+            bounding_box([Coord2::new(3, -1), Coord2::new(-2, 4), Coord2::new(0, 7)]),
+            Some((Coord2::new(-2, -1), Coord2::new(3, 7)))
+        );
+
+        let point = Coord3::new(1, 2, 3);
+        assert_eq!(bounding_box([point]), Some((point, point)));
+        assert_eq!(
+            bounding_box([
+                Coord3::new(1, 2, 3),
+                Coord3::new(3, -2, 1),
+                Coord3::new(2, 0, 5)
+            ]),
+            Some((Coord3::new(1, -2, 1), Coord3::new(3, 2, 5)))
+        );
+    }
+
+    #[test]
+    fn test_coord3_round_trip() -> Result<()> {
+        for s in ["2,2,2", "-1,0,12", "3,2,5"] {
+            let c: Coord3 = s.parse()?;
+            assert_eq!(c.to_string(), s);
+        }
+        assert_eq!("1,-2,3".parse::<Coord3>()?, Coord3::new(1, -2, 3));
+        assert!("1,2".parse::<Coord3>().is_err());
+        assert!("1".parse::<Coord3>().is_err());
+        assert!("1,2,a".parse::<Coord3>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_neighbors6() {
+        let c = Coord3::new(1, 2, 3);
+        let neighbors = c.neighbors6().collect::<Vec<_>>();
+        assert_eq!(neighbors.len(), 6);
+        assert!(neighbors
+            .iter()
+            .all(|n| n.x.abs_diff(c.x) + n.y.abs_diff(c.y) + n.z.abs_diff(c.z) == 1));
+    }
+
+    #[test]
+    fn test_direction_delta() {
+        let origin = Coord2::new(3, 3);
+        assert_eq!(origin + Direction::Up.delta(), Coord2::new(3, 2));
+        assert_eq!(origin + Direction::Right.delta(), Coord2::new(4, 3));
+        assert_eq!(origin + Direction::Down.delta(), Coord2::new(3, 4));
+        assert_eq!(origin + Direction::Left.delta(), Coord2::new(2, 3));
+        assert!(Direction::ALL
+            .iter()
+            .all(|d| d.delta().manhattan_distance(Coord2::default()) == 1));
+    }
+
+    #[test]
+    fn test_neighbors4() {
+        assert_eq!(
+            Coord2::new(1, 1).neighbors4().collect::<Vec<_>>(),
+            [
+                Coord2::new(1, 0),
+                Coord2::new(2, 1),
+                Coord2::new(1, 2),
+                Coord2::new(0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_neighbors8() {
+        let neighbors = Coord2::new(1, 1).neighbors8().collect::<Vec<_>>();
+        assert_eq!(neighbors.len(), 8);
+        assert_eq!(neighbors[0], Coord2::new(0, 0));
+        assert!(Coord2::new(1, 1)
+            .neighbors4()
+            .all(|n| neighbors.contains(&n)));
+        assert!(!neighbors.contains(&Coord2::new(1, 1)));
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        assert_eq!("498,4".parse::<Coord2>()?, Coord2::new(498, 4));
+        assert_eq!("-1,-2".parse::<Coord2>()?, Coord2::new(-1, -2));
+        assert!("498".parse::<Coord2>().is_err());
+        Ok(())
+    }
+}
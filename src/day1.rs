@@ -1,15 +1,15 @@
+use crate::Timer;
 use anyhow::Result;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
     let mut calories_by_elf = vec![0];
-    for line in io::BufReader::new(file).lines() {
-        let Some(calories) = line?.parse::<usize>().ok() else {
+    for line in input.lines() {
+        let Some(calories) = line.parse::<usize>().ok() else {
             calories_by_elf.push(0);
-            continue
+            continue;
         };
         *calories_by_elf.last_mut().unwrap() += calories;
     }
@@ -17,7 +17,11 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
     calories_by_elf.sort();
 
     Ok((
-        calories_by_elf.last().copied().unwrap_or(0),
-        Some(calories_by_elf.iter().copied().rev().take(3).sum()),
+        timer.part_a(|| calories_by_elf.last().copied().unwrap_or(0)),
+        Some(timer.part_b(|| calories_by_elf.iter().copied().rev().take(3).sum())),
     ))
 }
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
@@ -1,11 +1,13 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fmt;
+use std::fs;
+use std::iter;
 use std::path::Path;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy)]
-enum Op {
+pub enum Op {
     Noop,
     Addx(isize),
 }
@@ -24,58 +26,237 @@ impl FromStr for Op {
     }
 }
 
-fn compute_all_x(ops: &[Op]) -> Vec<isize> {
-    let mut x = vec![1];
-    for op in ops {
-        let cx = x.last().copied().unwrap();
-        match op {
-            Op::Noop => x.push(cx),
-            Op::Addx(n) => {
-                x.push(cx);
-                x.push(cx + n);
-            }
-        }
-    }
-    x
+/// Return the value of the X register during every cycle of the program, starting with the first
+pub fn x_per_cycle(ops: &[Op]) -> impl Iterator<Item = isize> + '_ {
+    ops.iter()
+        .scan(1, |x, op| {
+            let during = *x;
+            let num_cycles = match op {
+                Op::Noop => 1,
+                Op::Addx(n) => {
+                    *x += n;
+                    2
+                }
+            };
+            Some(iter::repeat_n(during, num_cycles))
+        })
+        .flatten()
 }
 
-fn part_a(ops: &[Op]) -> isize {
-    let key_cycles = [20, 60, 100, 140, 180, 220];
-    let x = compute_all_x(ops);
-    key_cycles
-        .into_iter()
-        .map(|c| (c as isize) * x[c - 1])
+/// Sum the signal strengths during the given cycles. Cycles outside of the program's execution
+/// are ignored
+pub fn signal_strength_sum(ops: &[Op], cycles: &[isize]) -> isize {
+    let x = x_per_cycle(ops).collect::<Vec<_>>();
+    cycles
+        .iter()
+        .copied()
+        .filter_map(|c| {
+            let i = usize::try_from(c - 1).ok()?;
+            Some(c * x.get(i)?)
+        })
         .sum()
 }
 
-fn part_b(ops: &[Op]) -> String {
+fn part_a(ops: &[Op]) -> isize {
+    signal_strength_sum(ops, &[20, 60, 100, 140, 180, 220])
+}
+
+/// The pixels drawn by the CRT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crt {
+    pixels: Vec<bool>,
+    width: usize,
+}
+
+impl Crt {
     const WIDTH: usize = 40;
-    let mut crt = [false; WIDTH * 6];
-    for ((cycle, x), pixel) in (0..WIDTH)
+    const HEIGHT: usize = 6;
+
+    /// Return true if the pixel at the given position is lit
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < self.width
+            && self
+                .pixels
+                .get(y * self.width + x)
+                .copied()
+                .unwrap_or(false)
+    }
+
+    /// Render the screen using the given characters for lit and dark pixels
+    pub fn render_with(&self, on: char, off: char) -> String {
+        self.pixels
+            .chunks_exact(self.width)
+            .map(|line| {
+                line.iter()
+                    .copied()
+                    .map(|p| if p { on } else { off })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for Crt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_with('#', ' '))
+    }
+}
+
+/// The glyphs of the font used by the CRT. Each glyph is 4 pixels wide and 6 pixels tall, stored
+/// row by row
+const FONT: &[(&str, char)] = &[
+    (".##.#..##..######..##..#", 'A'),
+    ("###.#..####.#..##..####.", 'B'),
+    (".##.#..##...#...#..#.##.", 'C'),
+    ("#####...###.#...#...####", 'E'),
+    ("#####...###.#...#...#...", 'F'),
+    (".##.#..##...#.###..#.###", 'G'),
+    ("#..##..######..##..##..#", 'H'),
+    ("..##...#...#...##..#.##.", 'J'),
+    ("#..##.#.##..#.#.#.#.#..#", 'K'),
+    ("#...#...#...#...#...####", 'L'),
+    (".##.#..##..##..##..#.##.", 'O'),
+    ("###.#..##..####.#...#...", 'P'),
+    ("###.#..##..####.#.#.#..#", 'R'),
+    (".####...#....##....####.", 'S'),
+    ("#..##..##..##..##..#.##.", 'U'),
+    ("####...#..#..#..#...####", 'Z'),
+];
+
+/// Read the letters spelled out by the CRT. Each letter is 4 pixels wide followed by a column of
+/// spacing. Returns None if the screen contains an unknown glyph
+pub fn ocr(crt: &Crt) -> Option<String> {
+    const GLYPH_WIDTH: usize = 4;
+    const GLYPH_SPACING: usize = 1;
+
+    if crt.width == 0 || crt.pixels.len() != crt.width * Crt::HEIGHT {
+        return None;
+    }
+    (0..crt.width)
+        .step_by(GLYPH_WIDTH + GLYPH_SPACING)
+        .map(|glyph_x| {
+            let glyph = (0..Crt::HEIGHT)
+                .flat_map(|y| {
+                    (glyph_x..glyph_x + GLYPH_WIDTH)
+                        .map(move |x| if crt.pixel(x, y) { '#' } else { '.' })
+                })
+                .collect::<String>();
+            FONT.iter()
+                .find(|(pattern, _)| *pattern == glyph)
+                .map(|&(_, letter)| letter)
+        })
+        .collect()
+}
+
+pub fn render_crt(ops: &[Op]) -> Crt {
+    let mut pixels = vec![false; Crt::WIDTH * Crt::HEIGHT];
+    for ((cycle, x), pixel) in (0..Crt::WIDTH)
         .cycle()
-        .zip(compute_all_x(ops))
-        .zip(crt.iter_mut())
+        .zip(x_per_cycle(ops))
+        .zip(pixels.iter_mut())
     {
         *pixel = (x - 1..=x + 1).contains(&(cycle as isize));
     }
+    Crt {
+        pixels,
+        width: Crt::WIDTH,
+    }
+}
 
-    crt.chunks_exact(WIDTH)
-        .map(|line| {
-            line.iter()
-                .copied()
-                .map(|p| if p { '#' } else { ' ' })
-                .collect::<String>()
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
+fn part_b(ops: &[Op]) -> String {
+    // Fall back to the raw screen if we can't read it
+    let crt = render_crt(ops);
+    ocr(&crt).unwrap_or_else(|| crt.to_string())
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(isize, Option<String>)> {
+    let ops = input.lines().map(str::parse).collect::<Result<Vec<Op>>>()?;
+
+    Ok((
+        timer.part_a(|| part_a(&ops)),
+        Some(timer.part_b(|| part_b(&ops))),
+    ))
 }
 
 pub fn main(path: &Path) -> Result<(isize, Option<String>)> {
-    let file = File::open(path)?;
-    let ops = io::BufReader::new(file)
-        .lines()
-        .map(|lr| lr?.parse())
-        .collect::<Result<Vec<Op>>>()?;
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok((part_a(&ops), Some(part_b(&ops))))
+    fn small_example() -> Vec<Op> {
+        ["noop", "addx 3", "addx -5"]
+            .into_iter()
+            .map(|l| l.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_x_per_cycle() {
+        let mut ops = small_example();
+        assert_eq!(x_per_cycle(&ops).collect::<Vec<_>>(), [1, 1, 1, 4, 4]);
+
+        // The last addx only affects X after its second cycle has finished
+        ops.push(Op::Noop);
+        assert_eq!(x_per_cycle(&ops).collect::<Vec<_>>(), [1, 1, 1, 4, 4, -1]);
+        assert_eq!(x_per_cycle(&[]).next(), None);
+    }
+
+    #[test]
+    fn test_signal_strength_sum() {
+        let ops = small_example();
+        assert_eq!(signal_strength_sum(&ops, &[1]), 1);
+        assert_eq!(signal_strength_sum(&ops, &[4, 5]), 4 * 4 + 5 * 4);
+        assert_eq!(signal_strength_sum(&ops, &[1, 2, 3, 4, 5]), 42);
+
+        // Cycles outside of the program are ignored
+        assert_eq!(signal_strength_sum(&ops, &[0, 6, 20, 220]), 0);
+    }
+
+    #[test]
+    fn test_render_crt() {
+        // X is 1 during the first three cycles and 4 during the next two
+        let crt = render_crt(&small_example());
+        assert!(crt.pixel(0, 0));
+        assert!(crt.pixel(1, 0));
+        assert!(crt.pixel(2, 0));
+        assert!(crt.pixel(3, 0));
+        assert!(crt.pixel(4, 0));
+        assert!(!crt.pixel(5, 0));
+        assert!(!crt.pixel(40, 0));
+        assert!(!crt.pixel(0, 6));
+        assert_eq!(
+            crt.render_with('#', '.').lines().next(),
+            Some(&*format!("#####{}", ".".repeat(35)))
+        );
+        assert_eq!(crt.to_string(), crt.render_with('#', ' '));
+    }
+
+    #[test]
+    fn test_ocr() {
+        let screen = [
+            "#### ####  ##  #### #### #    #  # #### ",
+            "#    #    #  #    # #    #    #  # #    ",
+            "###  ###  #      #  ###  #    #### ###  ",
+            "#    #    #     #   #    #    #  # #    ",
+            "#    #    #  # #    #    #    #  # #    ",
+            "#    ####  ##  #### #### #### #  # #### ",
+        ];
+        let mut crt = Crt {
+            pixels: screen
+                .iter()
+                .flat_map(|l| l.chars().map(|c| c == '#'))
+                .collect(),
+            width: 40,
+        };
+        assert_eq!(ocr(&crt).as_deref(), Some("FECZELHE"));
+
+        // Flipping a single pixel makes the glyph unrecognizable
+        crt.pixels[0] = false;
+        assert_eq!(ocr(&crt), None);
+    }
 }
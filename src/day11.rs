@@ -1,21 +1,44 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::Read;
+use std::fs;
 use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Debug, Clone)]
-enum Op {
-    Add(usize),
-    Mul(usize),
-    Pow,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operand {
+    Old,
+    Const(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Add,
+    Mul,
+}
+
+/// Worry level operation of the form `new = <lhs> <op> <rhs>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Op {
+    lhs: Operand,
+    op: Operator,
+    rhs: Operand,
+}
+
+/// How worry levels are kept from growing after each inspection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WorryMode {
+    /// Divide the worry level by the given number, rounding down
+    Divide(usize),
+    /// Keep the worry level modulo the least common multiple of every monkey's test divisor, which
+    /// doesn't change which monkey an item is thrown to
+    ModuloLcm,
 }
 
 #[derive(Debug, Clone)]
-struct Monkey {
+pub struct Monkey {
     items: VecDeque<usize>,
     op: Op,
     test_divisible_by: usize,
@@ -27,7 +50,7 @@ static MONKEY_RE: Lazy<Regex> = Lazy::new(|| {
     let pattern = [
         r"Monkey (\d+):",
         r"  Starting items: (?P<items>\d+(, \d+)*)",
-        r"  Operation: (?P<op>new = old [+*] \S+)",
+        r"  Operation: (?P<op>new = \S+ [+*] \S+)",
         r"  Test: divisible by (?P<test_divisible_by>\d+)",
         r"    If true: throw to monkey (?P<target_when_true>\d+)",
         r"    If false: throw to monkey (?P<target_when_false>\d+)",
@@ -36,22 +59,103 @@ static MONKEY_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(&pattern).unwrap()
 });
 
-impl FromStr for Op {
+/// Unsigned integer type that worry levels can be computed in
+trait WorryLevel: Copy {
+    fn from_usize(n: usize) -> Self;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+}
+
+impl WorryLevel for usize {
+    fn from_usize(n: usize) -> Self {
+        n
+    }
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        usize::checked_add(self, rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        usize::checked_mul(self, rhs)
+    }
+}
+
+impl WorryLevel for u128 {
+    fn from_usize(n: usize) -> Self {
+        n as u128
+    }
+
+    fn checked_add(self, rhs: Self) -> Option<Self> {
+        u128::checked_add(self, rhs)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        u128::checked_mul(self, rhs)
+    }
+}
+
+impl Operand {
+    fn value<T: WorryLevel>(self, old: T) -> T {
+        match self {
+            Self::Old => old,
+            Self::Const(n) => T::from_usize(n),
+        }
+    }
+}
+
+impl Op {
+    /// Apply the operation to the given worry level, modulo the given divisor
+    fn apply(&self, old: usize, modulo: usize) -> usize {
+        let lhs = self.lhs.value(old) % modulo;
+        let rhs = self.rhs.value(old) % modulo;
+        match self.op {
+            Operator::Add => (lhs + rhs) % modulo,
+            Operator::Mul => (lhs * rhs) % modulo,
+        }
+    }
+
+    /// Apply the operation to the given worry level, or return None if it overflows
+    fn checked_apply<T: WorryLevel>(&self, old: T) -> Option<T> {
+        let (lhs, rhs) = (self.lhs.value(old), self.rhs.value(old));
+        match self.op {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Mul => lhs.checked_mul(rhs),
+        }
+    }
+}
+
+impl FromStr for Operand {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s == "new = old * old" {
-            Ok(Self::Pow)
-        } else if let Some(term) = s.strip_prefix("new = old + ") {
-            Ok(Self::Add(term.parse()?))
-        } else if let Some(factor) = s.strip_prefix("new = old * ") {
-            Ok(Self::Mul(factor.parse()?))
+        if s == "old" {
+            Ok(Self::Old)
         } else {
-            Err(anyhow!("Invalid operation"))
+            Ok(Self::Const(s.parse()?))
         }
     }
 }
 
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(expr) = s.strip_prefix("new = ") else {
+            return Err(anyhow!("Invalid operation {:?}", s));
+        };
+        let (lhs, op, rhs) = match expr.split(' ').collect::<Vec<_>>()[..] {
+            [lhs, "+", rhs] => (lhs, Operator::Add, rhs),
+            [lhs, "*", rhs] => (lhs, Operator::Mul, rhs),
+            _ => return Err(anyhow!("Invalid operation {:?}", s)),
+        };
+        Ok(Self {
+            lhs: lhs.parse()?,
+            op,
+            rhs: rhs.parse()?,
+        })
+    }
+}
+
 impl FromStr for Monkey {
     type Err = anyhow::Error;
 
@@ -70,36 +174,46 @@ impl FromStr for Monkey {
     }
 }
 
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
 fn compute_monkey_business(
     mut monkeys: Vec<Monkey>,
     rounds: usize,
-    worry_level_divisor: usize,
-) -> usize {
-    // Find a divisor that is common for all monkeys
-    let common_divisor: usize = monkeys.iter().map(|m| m.test_divisible_by).product();
+    worry_mode: WorryMode,
+) -> Result<usize> {
+    // The trick we're using for ModuloLcm is:
+    //
+    // (x + y) % n = ((x % n) + (y % n)) % n
+    // (x * y) % n = ((x % n) * (y % n)) % n
+    //
+    // This is especially importand for monkey with the op `new = old * old` as the worry level
+    // grows to insane numbers without this "modulo compacting". Since the monkeys have different
+    // divisors and they are passing the items around we use a multiple of all of them
+    let lcm = monkeys
+        .iter()
+        .map(|m| m.test_divisible_by)
+        .fold(1, |lcm, d| lcm / gcd(lcm, d) * d);
 
     let mut num_inspections = vec![0; monkeys.len()];
     for _ in 0..rounds {
         for i in 0..monkeys.len() {
             while let Some(mut item) = monkeys[i].items.pop_front() {
                 num_inspections[i] += 1;
-
-                // I'm not sure it's matchematically valid to do the division here, but it works
-                // for both the example and my input ¯\_(ツ)_/¯. The trick we're using here is:
-                //
-                // (x + y) % n = ((x % n) + (y % n)) % n
-                // (x * y) % n = ((x % n) * (y % n)) % n
-                //
-                // This is especially importand for monkey with the op `new = old * old` as the
-                // worry level grows to insane numbers without this "modulo compacting".
-                //
-                // Since the monkeys have different divisors and they are passing the items around
-                // we find a common divisor that is compatible with all monkeys.
-                item = match monkeys[i].op {
-                    Op::Add(n) => (item + n) % common_divisor,
-                    Op::Mul(n) => (item * n) % common_divisor,
-                    Op::Pow => (item * item) % common_divisor,
-                } / worry_level_divisor;
+                item = match worry_mode {
+                    WorryMode::Divide(divisor) => {
+                        let Some(item) = monkeys[i].op.checked_apply(item) else {
+                            return Err(anyhow!("Worry level of monkey {} overflowed", i));
+                        };
+                        item / divisor
+                    }
+                    WorryMode::ModuloLcm => monkeys[i].op.apply(item, lcm),
+                };
 
                 let target = if item % monkeys[i].test_divisible_by == 0 {
                     monkeys[i].target_when_true
@@ -111,23 +225,61 @@ fn compute_monkey_business(
         }
     }
 
+    num_inspections.sort();
+    Ok(num_inspections.into_iter().rev().take(2).product())
+}
+
+/// Compute monkey business while keeping the true worry levels, dividing them by the given
+/// divisor after every inspection. Panics if a worry level overflows, so this is only feasible for
+/// a few rounds. Used to validate the modulo compacting in compute_monkey_business
+pub fn compute_monkey_business_exact(
+    monkeys: Vec<Monkey>,
+    rounds: usize,
+    worry_level_divisor: u128,
+) -> u128 {
+    let mut items = monkeys
+        .iter()
+        .map(|m| m.items.iter().map(|&i| i as u128).collect::<VecDeque<_>>())
+        .collect::<Vec<_>>();
+    let mut num_inspections = vec![0; monkeys.len()];
+    for _ in 0..rounds {
+        for (i, monkey) in monkeys.iter().enumerate() {
+            while let Some(item) = items[i].pop_front() {
+                num_inspections[i] += 1;
+                let Some(item) = monkey.op.checked_apply(item) else {
+                    panic!("Worry level of monkey {} overflowed", i);
+                };
+                let item = item / worry_level_divisor;
+                let target = if item.is_multiple_of(monkey.test_divisible_by as u128) {
+                    monkey.target_when_true
+                } else {
+                    monkey.target_when_false
+                };
+                items[target].push_back(item);
+            }
+        }
+    }
+
     num_inspections.sort();
     num_inspections.into_iter().rev().take(2).product()
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let mut input = String::new();
-    File::open(path)?.read_to_string(&mut input)?;
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
     let monkeys = input
         .split("\n\n")
         .map(Monkey::from_str)
         .collect::<Result<Vec<Monkey>>>()?;
     Ok((
-        compute_monkey_business(monkeys.clone(), 20, 3),
-        Some(compute_monkey_business(monkeys, 10_000, 1)),
+        timer.part_a(|| compute_monkey_business(monkeys.clone(), 20, WorryMode::Divide(3)))?,
+        Some(timer.part_b(|| compute_monkey_business(monkeys, 10_000, WorryMode::ModuloLcm))?),
     ))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,12 +321,88 @@ mod tests {
     }
 
     #[test]
-    fn test_example_a() {
-        assert_eq!(compute_monkey_business(monkeys(), 20, 3), 10_605);
+    fn test_parse_op() -> Result<()> {
+        assert_eq!(
+            "new = old * old".parse::<Op>()?,
+            Op {
+                lhs: Operand::Old,
+                op: Operator::Mul,
+                rhs: Operand::Old,
+            }
+        );
+        assert_eq!(
+            "new = old + 6".parse::<Op>()?,
+            Op {
+                lhs: Operand::Old,
+                op: Operator::Add,
+                rhs: Operand::Const(6),
+            }
+        );
+        assert_eq!(
+            "new = 3 * old".parse::<Op>()?,
+            Op {
+                lhs: Operand::Const(3),
+                op: Operator::Mul,
+                rhs: Operand::Old,
+            }
+        );
+        assert!("new = old - 3".parse::<Op>().is_err());
+        assert!("new = old * old + 1".parse::<Op>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_exact_matches_divide() -> Result<()> {
+        for rounds in [1, 5, 20] {
+            assert_eq!(
+                compute_monkey_business_exact(monkeys(), rounds, 3),
+                compute_monkey_business(monkeys(), rounds, WorryMode::Divide(3))? as u128
+            );
+        }
+        assert_eq!(compute_monkey_business_exact(monkeys(), 20, 3), 10_605);
+        Ok(())
+    }
+
+    #[test]
+    fn test_exact_matches_modulo_lcm() -> Result<()> {
+        // Without division the worry levels only fit in a u128 for the first few rounds
+        for rounds in [1, 2, 3] {
+            assert_eq!(
+                compute_monkey_business_exact(monkeys(), rounds, 1),
+                compute_monkey_business(monkeys(), rounds, WorryMode::ModuloLcm)? as u128
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_divide_overflow() {
+        // The worry level of an item squared every round overflows without any modulo
+        assert!(compute_monkey_business(monkeys(), 10_000, WorryMode::Divide(1)).is_err());
+    }
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(23, 19), 1);
+        assert_eq!(gcd(5, 0), 5);
+    }
+
+    #[test]
+    fn test_example_a() -> Result<()> {
+        assert_eq!(
+            compute_monkey_business(monkeys(), 20, WorryMode::Divide(3))?,
+            10_605
+        );
+        Ok(())
     }
 
     #[test]
-    fn test_example_b() {
-        assert_eq!(compute_monkey_business(monkeys(), 10_000, 1), 2_713_310_158);
+    fn test_example_b() -> Result<()> {
+        assert_eq!(
+            compute_monkey_business(monkeys(), 10_000, WorryMode::ModuloLcm)?,
+            2_713_310_158
+        );
+        Ok(())
     }
 }
@@ -1,53 +1,167 @@
+use crate::coord::Coord2;
+use crate::grid::Grid;
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
-struct Coord {
-    x: isize,
-    y: isize,
+/// Return the neighbors of the given position that are within the heightmap and can be climbed
+/// to, along with their heights. We can climb at most one step up but fall down any distance
+fn climbable_neighbors(
+    heightmap: &Grid<u8>,
+    pos: Coord2,
+) -> impl Iterator<Item = (Coord2, u8)> + '_ {
+    let max_height = heightmap.get(pos).map(|&h| h.saturating_add(1));
+    pos.neighbors4()
+        .filter_map(|n| heightmap.get(n).map(|&h| (n, h)))
+        .filter(move |&(_, h)| max_height.is_some_and(|max| h <= max))
 }
 
-impl Coord {
-    const fn new(x: isize, y: isize) -> Self {
-        Self { x, y }
-    }
+/// Outcome of a shortest path search along with how much of the heightmap it had to explore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Number of steps of the shortest path, or None if no end can be reached
+    pub num_steps: Option<usize>,
+    /// Number of positions whose neighbors were explored
+    pub num_expanded: usize,
+}
+
+/// Return the shortest path from any of the starts to any of the ends, including both the start
+/// and the end. The number of expanded positions is added to num_expanded
+fn find_shortest_path(
+    heightmap: &Grid<u8>,
+    starts: &[Coord2],
+    ends: &[Coord2],
+    num_expanded: &mut usize,
+) -> Option<Vec<Coord2>> {
+    // Use breadth first search to find the shortest path. We keep track of where we came from
+    // to be able to reconstruct the path once we reach an end
+    let mut came_from = HashMap::new();
+    let mut visited = starts.iter().copied().collect::<HashSet<_>>();
+    let mut to_visit = starts.iter().copied().collect::<VecDeque<_>>();
 
-    fn iter_neighbors(self) -> impl Iterator<Item = Self> {
-        [
-            Coord::new(self.x, self.y - 1), // Up
-            Coord::new(self.x + 1, self.y), // Right
-            Coord::new(self.x, self.y + 1), // Down
-            Coord::new(self.x - 1, self.y), // Left
-        ]
-        .into_iter()
+    while let Some(curr_pos) = to_visit.pop_front() {
+        if ends.contains(&curr_pos) {
+            let mut path = vec![curr_pos];
+            while let Some(&prev_pos) = came_from.get(path.last().unwrap()) {
+                path.push(prev_pos);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        *num_expanded += 1;
+        for (neighbor, _) in climbable_neighbors(heightmap, curr_pos) {
+            if !visited.insert(neighbor) {
+                continue;
+            }
+            came_from.insert(neighbor, curr_pos);
+            to_visit.push_back(neighbor);
+        }
     }
+    None
 }
 
-fn find_shortest_path_len(
-    heightmap: &HashMap<Coord, u8>,
-    start: Coord,
-    end: Coord,
+/// Return the number of steps of the shortest path from any of the starts to any of the ends
+pub fn find_shortest_path_len(
+    heightmap: &Grid<u8>,
+    starts: &[Coord2],
+    ends: &[Coord2],
 ) -> Option<usize> {
-    // Use breadth first search to find the shortest path
-    let mut visited = HashSet::new();
-    visited.insert(start);
-    let mut to_visit = VecDeque::new();
-    to_visit.push_back((0, start));
+    find_shortest_path_len_stats(heightmap, starts, ends).num_steps
+}
 
-    while let Some((num_moves, curr_pos)) = to_visit.pop_front() {
+/// Search like find_shortest_path_len and also report how many positions were expanded
+pub fn find_shortest_path_len_stats(
+    heightmap: &Grid<u8>,
+    starts: &[Coord2],
+    ends: &[Coord2],
+) -> SearchStats {
+    let mut num_expanded = 0;
+    let path = find_shortest_path(heightmap, starts, ends, &mut num_expanded);
+    SearchStats {
+        num_steps: path.map(|path| path.len() - 1),
+        num_expanded,
+    }
+}
+
+/// Return the number of steps of the shortest path from start to end. Uses A* with the Manhattan
+/// distance to the end as heuristic, which never overestimates since every step moves one tile
+pub fn find_shortest_path_astar(heightmap: &Grid<u8>, start: Coord2, end: Coord2) -> Option<usize> {
+    find_shortest_path_astar_stats(heightmap, start, end).num_steps
+}
+
+/// Search like find_shortest_path_astar and also report how many positions were expanded
+pub fn find_shortest_path_astar_stats(
+    heightmap: &Grid<u8>,
+    start: Coord2,
+    end: Coord2,
+) -> SearchStats {
+    let mut num_expanded = 0;
+    let mut best_num_steps = HashMap::new();
+    best_num_steps.insert(start, 0);
+    // Many positions share the same estimate when the heightmap allows walking straight towards
+    // the end. Ties are broken in favor of the position furthest along its path, otherwise A*
+    // expands them in the same order as breadth first search
+    let mut to_visit = BinaryHeap::new();
+    to_visit.push((Reverse(start.manhattan_distance(end)), 0, start));
+
+    while let Some((_, num_steps, curr_pos)) = to_visit.pop() {
         if curr_pos == end {
+            return SearchStats {
+                num_steps: Some(num_steps),
+                num_expanded,
+            };
+        }
+        // Skip positions that we've already found a shorter path to
+        if best_num_steps
+            .get(&curr_pos)
+            .is_some_and(|&best| best < num_steps)
+        {
+            continue;
+        }
+        num_expanded += 1;
+        for (neighbor, _) in climbable_neighbors(heightmap, curr_pos) {
+            let next_num_steps = num_steps + 1;
+            if best_num_steps
+                .get(&neighbor)
+                .is_some_and(|&best| best <= next_num_steps)
+            {
+                continue;
+            }
+            best_num_steps.insert(neighbor, next_num_steps);
+            to_visit.push((
+                Reverse(next_num_steps + neighbor.manhattan_distance(end)),
+                next_num_steps,
+                neighbor,
+            ));
+        }
+    }
+    SearchStats {
+        num_steps: None,
+        num_expanded,
+    }
+}
+
+fn part_b(heightmap: &Grid<u8>, ends: &[Coord2]) -> Option<usize> {
+    // Instead of searching from every possible start we search backwards from the ends until we
+    // find the closest tile at the lowest elevation. Moving backwards from u to v is allowed if
+    // moving forward from v to u would be
+    let mut visited = ends.iter().copied().collect::<HashSet<_>>();
+    let mut to_visit = ends.iter().map(|&end| (0, end)).collect::<VecDeque<_>>();
+
+    while let Some((num_moves, curr_pos)) = to_visit.pop_front() {
+        let height = *heightmap.get(curr_pos)?;
+        if height == 0 {
             return Some(num_moves);
         }
-        let height = heightmap.get(&curr_pos).unwrap();
 
         for (neighbor, neighbor_height) in curr_pos
-            .iter_neighbors()
-            .filter_map(|n| heightmap.get(&n).map(|h| (n, *h)))
+            .neighbors4()
+            .filter_map(|n| heightmap.get(n).map(|h| (n, *h)))
         {
-            if neighbor_height > height + 1 || !visited.insert(neighbor) {
+            if height > neighbor_height + 1 || !visited.insert(neighbor) {
                 continue;
             }
             to_visit.push_back((num_moves + 1, neighbor));
@@ -56,66 +170,72 @@ fn find_shortest_path_len(
     None
 }
 
-fn part_b(heightmap: &HashMap<Coord, u8>, end: Coord) -> Option<usize> {
-    heightmap
-        .iter()
-        .filter_map(|(&c, &h)| (h == 0).then_some(c))
-        .filter_map(|start| find_shortest_path_len(heightmap, start, end))
-        .min()
-}
-
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let mut heightmap: HashMap<Coord, u8> = HashMap::new();
-    let mut start = None;
-    let mut end = None;
-    for (y, lr) in io::BufReader::new(File::open(path)?).lines().enumerate() {
-        for (x, tile) in lr?.chars().enumerate() {
-            let coord = Coord::new(x.try_into()?, y.try_into()?);
-            match tile {
-                'S' => {
-                    start = Some(coord);
-                    heightmap.insert(coord, 0);
-                }
-                'E' => {
-                    end = Some(coord);
-                    heightmap.insert(coord, 25);
-                }
-                _ if ('a'..='z').contains(&tile) => {
-                    heightmap.insert(coord, u8::try_from(tile)? - 97);
-                }
-                _ => return Err(anyhow!("Invalid heightmap character {:?}", tile)),
-            }
+/// Parse the heightmap using the given characters to mark the starts and ends. Starts are at the
+/// lowest elevation and ends at the highest. Return the heightmap together with every start and
+/// every end in reading order
+pub fn parse_heightmap(
+    input: &str,
+    start_char: char,
+    end_char: char,
+) -> Result<(Grid<u8>, Vec<Coord2>, Vec<Coord2>)> {
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+    let heightmap = Grid::from_str_with(input, |coord, tile| match tile {
+        _ if tile == start_char => {
+            starts.push(coord);
+            Ok(Some(0))
         }
-    }
+        _ if tile == end_char => {
+            ends.push(coord);
+            Ok(Some(25))
+        }
+        _ if tile.is_ascii_lowercase() => Ok(Some(u8::try_from(tile)? - 97)),
+        _ => Err(anyhow!("Invalid heightmap character {:?}", tile)),
+    })?;
 
-    let Some(start) = start else {
+    if starts.is_empty() {
         return Err(anyhow!("Found no start position"));
-    };
-    let Some(end) = end else {
+    }
+    if ends.is_empty() {
         return Err(anyhow!("Found no end position"));
-    };
+    }
+    Ok((heightmap, starts, ends))
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let (heightmap, starts, ends) = parse_heightmap(input, 'S', 'E')?;
 
     Ok((
-        find_shortest_path_len(&heightmap, start, end)
+        timer
+            .part_a(|| find_shortest_path_len(&heightmap, &starts, &ends))
             .ok_or_else(|| anyhow!("Found no path for part A"))?,
-        Some(part_b(&heightmap, end).ok_or_else(|| anyhow!("Found no paths for part A"))?),
+        Some(
+            timer
+                .part_b(|| part_b(&heightmap, &ends))
+                .ok_or_else(|| anyhow!("Found no paths for part B"))?,
+        ),
     ))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const END: Coord = Coord::new(5, 2);
+    const END: Coord2 = Coord2::new(5, 2);
 
-    fn example_heightmap() -> HashMap<Coord, u8> {
+    fn example_heightmap() -> Grid<u8> {
         ["aabqponm", "abcryxxl", "accszzxk", "acctuvwj", "abdefghi"]
             .into_iter()
             .enumerate()
             .flat_map(|(y, line)| {
                 line.chars().enumerate().map(move |(x, t)| {
                     (
-                        Coord::new(x as isize, y as isize),
+                        Coord2::new(x as isize, y as isize),
                         u8::try_from(t).unwrap() - 97,
                     )
                 })
@@ -123,16 +243,166 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn test_climbable_neighbors() {
+        let heightmap = example_heightmap();
+
+        // The r to the right is a cliff that can't be climbed
+        assert_eq!(
+            climbable_neighbors(&heightmap, Coord2::new(2, 1)).collect::<Vec<_>>(),
+            [
+                (Coord2::new(2, 0), 1),
+                (Coord2::new(2, 2), 2),
+                (Coord2::new(1, 1), 1)
+            ]
+        );
+
+        // From the top of the cliff we can fall back down, but the y to the right is too high
+        assert_eq!(
+            climbable_neighbors(&heightmap, Coord2::new(3, 1))
+                .map(|(_, h)| h)
+                .collect::<Vec<_>>(),
+            [16, 18, 2]
+        );
+
+        // Positions outside of the heightmap are ignored
+        assert_eq!(
+            climbable_neighbors(&heightmap, Coord2::new(0, 0)).collect::<Vec<_>>(),
+            [(Coord2::new(1, 0), 0), (Coord2::new(0, 1), 0)]
+        );
+        assert_eq!(
+            climbable_neighbors(&heightmap, Coord2::new(-1, 0)).count(),
+            0
+        );
+    }
+
     #[test]
     fn test_example_a() {
         assert_eq!(
-            find_shortest_path_len(&example_heightmap(), Coord::new(0, 0), END),
+            find_shortest_path_len(&example_heightmap(), &[Coord2::new(0, 0)], &[END]),
             Some(31)
         );
     }
 
+    #[test]
+    fn test_astar_matches_bfs() {
+        let heightmap = example_heightmap();
+        let start = Coord2::new(0, 0);
+        assert_eq!(find_shortest_path_astar(&heightmap, start, END), Some(31));
+
+        // Every tile of the example to every other tile, including unreachable ones
+        for (from, _) in heightmap.iter() {
+            for (to, _) in heightmap.iter() {
+                assert_eq!(
+                    find_shortest_path_astar(&heightmap, from, to),
+                    find_shortest_path_len(&heightmap, &[from], &[to])
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_astar_expands_fewer_nodes() {
+        // Same shape as the large heightmap benchmark: it slopes upwards towards the end, with
+        // some dips that don't block the way
+        let size = 100;
+        let heightmap = (0..size)
+            .flat_map(|x| (0..size).map(move |y| Coord2::new(x, y)))
+            .map(|c| {
+                let height = ((c.x + c.y) * 26 / (2 * size)).min(25) as u8;
+                (c, height.saturating_sub(((c.x * c.y) % 7 == 0) as u8))
+            })
+            .collect::<Grid<u8>>();
+        let start = Coord2::new(0, 0);
+        let end = Coord2::new(size - 1, size - 1);
+
+        let bfs = find_shortest_path_len_stats(&heightmap, &[start], &[end]);
+        let astar = find_shortest_path_astar_stats(&heightmap, start, end);
+        assert_eq!(bfs.num_steps, Some(2 * (size as usize - 1)));
+        assert_eq!(astar.num_steps, bfs.num_steps);
+        assert!(
+            astar.num_expanded < bfs.num_expanded / 10,
+            "A* expanded {} positions and BFS {}",
+            astar.num_expanded,
+            bfs.num_expanded
+        );
+
+        // Searches that can't reach the end still report how much they explored
+        let example = example_heightmap();
+        let unreachable = Coord2::new(-1, -1);
+        let bfs = find_shortest_path_len_stats(&example, &[start], &[unreachable]);
+        assert_eq!(bfs.num_steps, None);
+        assert_eq!(bfs.num_expanded, example.len());
+    }
+
+    #[test]
+    fn test_example_path() {
+        let start = Coord2::new(0, 0);
+        let path = find_shortest_path(&example_heightmap(), &[start], &[END], &mut 0).unwrap();
+        assert_eq!(path.len() - 1, 31);
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&END));
+        for (a, b) in path.iter().zip(path.iter().skip(1)) {
+            assert!(a.neighbors4().any(|n| n == *b));
+        }
+    }
+
+    /// Run a separate search from every tile at the lowest elevation
+    fn part_b_brute_force(heightmap: &Grid<u8>, end: Coord2) -> Option<usize> {
+        heightmap
+            .iter()
+            .filter_map(|(c, &h)| (h == 0).then_some(c))
+            .filter_map(|start| find_shortest_path_len(heightmap, &[start], &[end]))
+            .min()
+    }
+
     #[test]
     fn test_example_b() {
-        assert_eq!(part_b(&example_heightmap(), END), Some(29));
+        assert_eq!(part_b(&example_heightmap(), &[END]), Some(29));
+        assert_eq!(part_b_brute_force(&example_heightmap(), END), Some(29));
+    }
+
+    #[test]
+    fn test_part_b_matches_brute_force() {
+        // Generate a larger pseudo random heightmap that is mostly climbable
+        let mut seed: u64 = 2022;
+        let mut heightmap = Grid::new();
+        for y in 0..30 {
+            for x in 0..40 {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                let noise = ((seed >> 33) % 3) as isize;
+                let height = ((x + y) / 3 + noise - 1).clamp(0, 25);
+                heightmap.insert(Coord2::new(x, y), height as u8);
+            }
+        }
+        let end = Coord2::new(39, 29);
+
+        let expected = part_b_brute_force(&heightmap, end);
+        assert!(expected.is_some());
+        assert_eq!(part_b(&heightmap, &[end]), expected);
+    }
+
+    #[test]
+    fn test_custom_markers() -> Result<()> {
+        // Two starts marked with @ and two peaks marked with Z. The first start is 26 steps from
+        // the closest peak while the second start is 25 steps from the peak on its row
+        let input = concat!(
+            "@abcdefghijklmnopqrstuvwxyZ\n",
+            "Zyxwvutsrqponmlkjihgfedcb@a\n",
+        );
+        let (heightmap, starts, ends) = parse_heightmap(input, '@', 'Z')?;
+        assert_eq!(starts, vec![Coord2::new(0, 0), Coord2::new(25, 1)]);
+        assert_eq!(ends, vec![Coord2::new(26, 0), Coord2::new(0, 1)]);
+        assert_eq!(heightmap.get(Coord2::new(0, 1)), Some(&25));
+        assert_eq!(find_shortest_path_len(&heightmap, &starts, &ends), Some(25));
+        assert_eq!(
+            find_shortest_path_len(&heightmap, &starts[..1], &ends),
+            Some(26)
+        );
+
+        // The default markers are invalid characters in this map
+        assert!(parse_heightmap(input, 'S', 'E').is_err());
+        assert!(parse_heightmap("abc", 'a', 'E').is_err());
+        Ok(())
     }
 }
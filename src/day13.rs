@@ -1,8 +1,8 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use chumsky::prelude::*;
 use std::cmp::Ordering;
-use std::fs::File;
-use std::io::Read;
+use std::fs;
 use std::iter;
 use std::path::Path;
 
@@ -17,11 +17,16 @@ fn parser() -> impl Parser<char, Packet, Error = Simple<char>> {
         p.separated_by(just(','))
             .delimited_by(just('['), just(']'))
             .map(Packet::List)
-            .or(text::int(10).from_str().unwrapped().map(Packet::Int))
+            .or(text::int(10).try_map(|s: String, span| {
+                s.parse()
+                    .map(Packet::Int)
+                    .map_err(|e| Simple::custom(span, format!("Invalid integer: {}", e)))
+            }))
     })
+    .then_ignore(end())
 }
 
-fn is_in_order(left: &Vec<Packet>, right: &Vec<Packet>) -> Ordering {
+fn is_in_order(left: &[Packet], right: &[Packet]) -> Ordering {
     for pair in left.iter().zip(right) {
         match pair {
             (Packet::Int(l), Packet::Int(r)) => match l.cmp(r) {
@@ -35,13 +40,13 @@ fn is_in_order(left: &Vec<Packet>, right: &Vec<Packet>) -> Ordering {
                 }
             }
             (Packet::List(l), Packet::Int(r)) => {
-                let order = is_in_order(l, &vec![Packet::Int(*r)]);
+                let order = is_in_order(l, &[Packet::Int(*r)]);
                 if order.is_ne() {
                     return order;
                 }
             }
             (Packet::Int(l), Packet::List(r)) => {
-                let order = is_in_order(&vec![Packet::Int(*l)], r);
+                let order = is_in_order(&[Packet::Int(*l)], r);
                 if order.is_ne() {
                     return order;
                 }
@@ -84,21 +89,103 @@ fn part_b(pairs: &[(Vec<Packet>, Vec<Packet>)]) -> usize {
     (divider_1_idx.unwrap() + 1) * (divider_2_idx.unwrap() + 1)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let mut input = String::new();
-    File::open(path)?.read_to_string(&mut input)?;
+fn parse_packet(
+    packet_parser: &impl Parser<char, Packet, Error = Simple<char>>,
+    s: &str,
+) -> Result<Vec<Packet>> {
+    match packet_parser.parse(s) {
+        Ok(Packet::List(packet)) => Ok(packet),
+        Ok(Packet::Int(_)) => Err(anyhow!("Packet {:?} is not a list", s)),
+        Err(errors) => Err(anyhow!(
+            "Invalid packet {:?} ({})",
+            s,
+            errors
+                .into_iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
 
-    let mut pairs = Vec::new();
+fn parse_pairs(input: &str) -> Result<Vec<(Vec<Packet>, Vec<Packet>)>> {
     let packet_parser = parser();
-    for pair in input.split("\n\n").map(|pair_str| {
-        pair_str
-            .split_once('\n')
-            .ok_or_else(|| anyhow!("Pair must have a single line break"))
-    }) {
-        let (left, right) = pair?;
-        let Packet::List(left) = packet_parser.parse(left).unwrap() else { panic!(); };
-        let Packet::List(right) = packet_parser.parse(right).unwrap() else { panic!(); };
-        pairs.push((left, right));
+    input
+        .split("\n\n")
+        .map(|pair_str| {
+            let Some((left, right)) = pair_str.trim().split_once('\n') else {
+                return Err(anyhow!("Pair must have a single line break"));
+            };
+            Ok((
+                parse_packet(&packet_parser, left)?,
+                parse_packet(&packet_parser, right)?,
+            ))
+        })
+        .collect()
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let pairs = parse_pairs(input)?;
+    Ok((
+        timer.part_a(|| part_a(&pairs)),
+        Some(timer.part_b(|| part_b(&pairs))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = concat!(
+        "[1,1,3,1,1]\n",
+        "[1,1,5,1,1]\n",
+        "\n",
+        "[[1],[2,3,4]]\n",
+        "[[1],4]\n",
+        "\n",
+        "[9]\n",
+        "[[8,7,6]]\n",
+        "\n",
+        "[[4,4],4,4]\n",
+        "[[4,4],4,4,4]\n",
+        "\n",
+        "[7,7,7,7]\n",
+        "[7,7,7]\n",
+        "\n",
+        "[]\n",
+        "[3]\n",
+        "\n",
+        "[[[]]]\n",
+        "[[]]\n",
+        "\n",
+        "[1,[2,[3,[4,[5,6,7]]]],8,9]\n",
+        "[1,[2,[3,[4,[5,6,0]]]],8,9]\n",
+    );
+
+    #[test]
+    fn test_example_a() -> Result<()> {
+        assert_eq!(part_a(&parse_pairs(EXAMPLE)?), 13);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_b() -> Result<()> {
+        assert_eq!(part_b(&parse_pairs(EXAMPLE)?), 140);
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_packets() {
+        assert!(parse_pairs("[1,2\n[3]").is_err());
+        assert!(parse_pairs("[1,2]\n[3]]").is_err());
+        assert!(parse_pairs("[1,2]\n3").is_err());
+        assert!(parse_pairs("[1,2]").is_err());
+        assert!(parse_pairs("[99999999999999999999999]\n[1]\n").is_err());
+        assert!(solve("[99999999999999999999999]\n[1]\n", &mut Timer::new()).is_err());
     }
-    Ok((part_a(&pairs), Some(part_b(&pairs))))
 }
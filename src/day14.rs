@@ -1,58 +1,53 @@
+use crate::coord::{bounding_box, Coord2};
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
-struct Coord {
-    x: isize,
-    y: isize,
-}
-
-impl Coord {
-    const fn new(x: isize, y: isize) -> Self {
-        Self { x, y }
-    }
-
+impl Coord2 {
     fn iter_fall_coords(self) -> impl Iterator<Item = Self> {
         [
-            Coord::new(self.x, self.y + 1),     // Down
-            Coord::new(self.x - 1, self.y + 1), // Down left
-            Coord::new(self.x + 1, self.y + 1), // Down right
+            Coord2::new(self.x, self.y + 1),     // Down
+            Coord2::new(self.x - 1, self.y + 1), // Down left
+            Coord2::new(self.x + 1, self.y + 1), // Down right
         ]
         .into_iter()
     }
 }
 
-impl FromStr for Coord {
-    type Err = anyhow::Error;
+/// Position the sand is poured from in the puzzle
+const SAND_SOURCE: Coord2 = Coord2::new(500, 0);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let Some((x_str, y_str)) = s.split_once(',') else {
-            return Err(anyhow!("Malformed coordinate {:?}", s));
-        };
-        Ok(Self {
-            x: x_str.parse()?,
-            y: y_str.parse()?,
-        })
-    }
+/// What the sand ends up on once it falls below the lowest rock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bottom {
+    /// Sand falls forever
+    Abyss,
+    /// Infinite floor two tiles below the lowest rock
+    Floor,
 }
 
-fn part_a(rocks: &HashSet<Coord>) -> Result<usize> {
+/// Pour sand until it starts falling into the abyss and return where the sand came to rest
+fn pour_into_abyss(rocks: &HashSet<Coord2>, source: Coord2) -> Result<HashSet<Coord2>> {
     let max_y = rocks.iter().copied().map(|r| r.y).max().unwrap_or(0);
-    let mut blocked = rocks.clone();
+    let mut sand = HashSet::new();
+    let is_blocked = |c: &Coord2, sand: &HashSet<Coord2>| rocks.contains(c) || sand.contains(c);
 
-    for num_grains in 0.. {
-        let mut grain = Coord::new(500, 0);
+    for _ in 0.. {
+        // The sand may pile up to the source if it's poured into a closed container
+        if is_blocked(&source, &sand) {
+            return Ok(sand);
+        }
+        let mut grain = source;
         loop {
-            let Some(next_grain) = grain.iter_fall_coords().find(|c| !blocked.contains(c)) else {
-                blocked.insert(grain);
+            let Some(next_grain) = grain.iter_fall_coords().find(|c| !is_blocked(c, &sand)) else {
+                sand.insert(grain);
                 break;
             };
             if next_grain.y > max_y {
-                return Ok(num_grains);
+                return Ok(sand);
             }
             grain = next_grain;
         }
@@ -61,33 +56,85 @@ fn part_a(rocks: &HashSet<Coord>) -> Result<usize> {
     Err(anyhow!("Sand grain overflow"))
 }
 
-fn part_b(rocks: &HashSet<Coord>) -> Result<usize> {
-    let max_y = rocks.iter().copied().map(|r| r.y).max().unwrap_or(0) + 2;
-    let mut blocked = rocks.clone();
+/// Pour sand onto the floor until the source is blocked and return where the sand came to rest
+fn pour_onto_floor(rocks: &HashSet<Coord2>, source: Coord2) -> HashSet<Coord2> {
+    // Since sand will keep piling up until the source is blocked, every tile that is reachable by
+    // falling from the source will eventually be filled with sand. This means that we can use a
+    // flood fill to find them instead of simulating every grain
+    let floor_y = rocks.iter().copied().map(|r| r.y).max().unwrap_or(0) + 2;
+    let mut sand = HashSet::new();
+    if rocks.contains(&source) || source.y >= floor_y {
+        return sand;
+    }
 
-    for num_grains in 0.. {
-        let mut grain = Coord::new(500, 0);
-        if blocked.contains(&grain) {
-            return Ok(num_grains);
-        }
-        loop {
-            let Some(next_grain) = grain.iter_fall_coords().find(|c| !blocked.contains(c) && c.y < max_y) else {
-                blocked.insert(grain);
-                break;
-            };
-            grain = next_grain;
+    let mut to_visit = vec![source];
+    sand.insert(source);
+    while let Some(grain) = to_visit.pop() {
+        for next_grain in grain.iter_fall_coords() {
+            if next_grain.y >= floor_y || rocks.contains(&next_grain) || !sand.insert(next_grain) {
+                continue;
+            }
+            to_visit.push(next_grain);
         }
     }
-    // This should never happen unless the input is malformed
-    Err(anyhow!("Sand grain overflow"))
+    sand
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+/// Pour sand from the source until no more sand comes to rest. Return the number of grains that
+/// came to rest together with their positions
+pub fn simulate(
+    rocks: &HashSet<Coord2>,
+    source: Coord2,
+    bottom: Bottom,
+) -> Result<(usize, HashSet<Coord2>)> {
+    let sand = match bottom {
+        Bottom::Abyss => pour_into_abyss(rocks, source)?,
+        Bottom::Floor => pour_onto_floor(rocks, source),
+    };
+    Ok((sand.len(), sand))
+}
+
+fn part_a(rocks: &HashSet<Coord2>, source: Coord2) -> Result<usize> {
+    Ok(pour_into_abyss(rocks, source)?.len())
+}
+
+fn part_b(rocks: &HashSet<Coord2>, source: Coord2) -> usize {
+    pour_onto_floor(rocks, source).len()
+}
+
+/// Draw the cave within the bounding box of all rocks, sand and the sand source
+pub fn render(rocks: &HashSet<Coord2>, sand: &HashSet<Coord2>, source: Coord2) -> String {
+    let all_coords = rocks.iter().chain(sand.iter()).copied().chain([source]);
+    let (min, max) = bounding_box(all_coords).unwrap();
+
+    (min.y..=max.y)
+        .map(|y| {
+            (min.x..=max.x)
+                .map(|x| {
+                    let c = Coord2::new(x, y);
+                    if rocks.contains(&c) {
+                        '#'
+                    } else if sand.contains(&c) {
+                        'o'
+                    } else if c == source {
+                        '+'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parse the rock paths of the scan into the set of tiles occupied by rock
+pub fn parse(input: &str) -> Result<HashSet<Coord2>> {
     let mut rocks = HashSet::new();
-    for lr in io::BufReader::new(File::open(path)?).lines() {
-        let corners = lr?
+    for line in input.lines() {
+        let corners = line
             .split(" -> ")
-            .map(Coord::from_str)
+            .map(Coord2::from_str)
             .collect::<Result<Vec<_>>>()?;
         let mut corners = corners.into_iter();
 
@@ -95,27 +142,187 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
             return Err(anyhow!("Got a line without any corners"));
         };
         for target in corners {
-            if source.x == target.x {
-                let step_y = (target.y - source.y).clamp(-1, 1);
-                rocks.extend(
-                    (0..)
-                        .map(|i| Coord::new(source.x, source.y + i * step_y))
-                        .take_while(|&c| c != target),
-                );
-            } else if source.y == target.y {
-                let step_x = (target.x - source.x).clamp(-1, 1);
-                rocks.extend(
-                    (0..)
-                        .map(|i| Coord::new(source.x + i * step_x, source.y))
-                        .take_while(|&c| c != target),
-                );
-            } else {
-                return Err(anyhow!("Diagonal line from {:?} to {:?}", source, target));
+            // Lines are either horizontal, vertical or diagonal at 45 degrees
+            let delta = target - source;
+            if delta.x != 0 && delta.y != 0 && delta.x.abs() != delta.y.abs() {
+                return Err(anyhow!(
+                    "Line from {:?} to {:?} is neither straight nor diagonal",
+                    source,
+                    target
+                ));
             }
+            let step = Coord2::new(delta.x.signum(), delta.y.signum());
+            rocks.extend(
+                (0..)
+                    .map(|i| Coord2::new(source.x + i * step.x, source.y + i * step.y))
+                    .take_while(|&c| c != target),
+            );
             rocks.insert(target);
             source = target;
         }
     }
+    Ok(rocks)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let rocks = parse(input)?;
+    Ok((
+        timer.part_a(|| part_a(&rocks, SAND_SOURCE))?,
+        Some(timer.part_b(|| part_b(&rocks, SAND_SOURCE))),
+    ))
+}
 
-    Ok((part_a(&rocks)?, Some(part_b(&rocks)?)))
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = concat!(
+        "498,4 -> 498,6 -> 496,6\n",
+        "503,4 -> 502,4 -> 502,9 -> 494,9\n",
+    );
+
+    /// Drop the sand one grain at a time until the source is blocked
+    fn part_b_grain_by_grain(rocks: &HashSet<Coord2>, source: Coord2) -> Result<usize> {
+        let max_y = rocks.iter().copied().map(|r| r.y).max().unwrap_or(0) + 2;
+        let mut blocked = rocks.clone();
+
+        for num_grains in 0.. {
+            let mut grain = source;
+            if blocked.contains(&grain) {
+                return Ok(num_grains);
+            }
+            loop {
+                let Some(next_grain) = grain
+                    .iter_fall_coords()
+                    .find(|c| !blocked.contains(c) && c.y < max_y)
+                else {
+                    blocked.insert(grain);
+                    break;
+                };
+                grain = next_grain;
+            }
+        }
+        Err(anyhow!("Sand grain overflow"))
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let rocks = parse(EXAMPLE)?;
+        assert_eq!(rocks.len(), 20);
+        assert!(rocks.contains(&Coord2::new(498, 5)));
+        assert!(rocks.contains(&Coord2::new(494, 9)));
+        assert!(parse("498,4 -> 499,6").is_err());
+        assert!(parse("498;4").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_diagonal() -> Result<()> {
+        assert_eq!(
+            parse("498,4 -> 500,6")?,
+            [
+                Coord2::new(498, 4),
+                Coord2::new(499, 5),
+                Coord2::new(500, 6),
+            ]
+            .into_iter()
+            .collect()
+        );
+        assert_eq!(parse("500,6 -> 498,4")?, parse("498,4 -> 500,6")?);
+        assert_eq!(parse("498,6 -> 500,4 -> 500,5")?.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_a() -> Result<()> {
+        assert_eq!(part_a(&parse(EXAMPLE)?, SAND_SOURCE)?, 24);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_b() -> Result<()> {
+        let rocks = parse(EXAMPLE)?;
+        assert_eq!(part_b(&rocks, SAND_SOURCE), 93);
+        assert_eq!(part_b_grain_by_grain(&rocks, SAND_SOURCE)?, 93);
+        Ok(())
+    }
+
+    #[test]
+    fn test_flood_fill_matches_grain_by_grain() -> Result<()> {
+        // A small bowl with a pillar in it
+        let mut rocks = HashSet::new();
+        rocks.extend((495..=505).map(|x| Coord2::new(x, 6)));
+        rocks.extend((3..=6).map(|y| Coord2::new(495, y)));
+        rocks.extend((3..=6).map(|y| Coord2::new(505, y)));
+        rocks.extend((4..=6).map(|y| Coord2::new(501, y)));
+        assert_eq!(
+            part_b(&rocks, SAND_SOURCE),
+            part_b_grain_by_grain(&rocks, SAND_SOURCE)?
+        );
+
+        // No rocks at all
+        assert_eq!(part_b(&HashSet::new(), SAND_SOURCE), 4);
+        assert_eq!(part_b_grain_by_grain(&HashSet::new(), SAND_SOURCE)?, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulate() -> Result<()> {
+        let rocks = parse(EXAMPLE)?;
+        let (num_grains, sand) = simulate(&rocks, SAND_SOURCE, Bottom::Abyss)?;
+        assert_eq!(num_grains, 24);
+        assert_eq!(sand.len(), num_grains);
+        assert!(sand.contains(&Coord2::new(500, 8)));
+        assert!(sand.is_disjoint(&rocks));
+
+        let (num_grains, sand) = simulate(&rocks, SAND_SOURCE, Bottom::Floor)?;
+        assert_eq!(num_grains, 93);
+        assert_eq!(sand.len(), num_grains);
+        assert!(sand.contains(&SAND_SOURCE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_source() -> Result<()> {
+        // Sand poured into a closed bowl fills it and then piles up to the source
+        let bowl = parse("8,1 -> 8,3 -> 12,3 -> 12,1")?;
+        assert_eq!(part_a(&bowl, Coord2::new(10, 0))?, 7);
+
+        let rocks = parse(EXAMPLE)?;
+        let source = Coord2::new(497, 2);
+        assert_eq!(
+            part_b(&rocks, source),
+            part_b_grain_by_grain(&rocks, source)?
+        );
+        assert_ne!(part_b(&rocks, source), part_b(&rocks, SAND_SOURCE));
+
+        // Nothing can be poured from inside a rock or below the floor
+        assert_eq!(part_b(&rocks, Coord2::new(498, 5)), 0);
+        assert_eq!(part_b(&rocks, Coord2::new(500, 11)), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() {
+        let rocks = [
+            Coord2::new(498, 3),
+            Coord2::new(499, 3),
+            Coord2::new(501, 3),
+        ]
+        .into_iter()
+        .collect();
+        let sand = [Coord2::new(500, 2), Coord2::new(500, 3)]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            render(&rocks, &sand, SAND_SOURCE),
+            ["..+.", "....", "..o.", "##o#"].join("\n")
+        );
+        assert_eq!(render(&HashSet::new(), &HashSet::new(), SAND_SOURCE), "+");
+    }
 }
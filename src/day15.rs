@@ -1,10 +1,11 @@
+use crate::coord::Coord2;
+use crate::ranges;
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::cmp::Reverse;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::ops::RangeInclusive;
 use std::path::Path;
 
@@ -13,21 +14,7 @@ static REPORT_LINE_RE: Lazy<Regex> = Lazy::new(|| {
         .unwrap()
 });
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coord {
-    x: isize,
-    y: isize,
-}
-
-impl Coord {
-    fn new(x: isize, y: isize) -> Self {
-        Self { x, y }
-    }
-
-    fn manhattan_distance(&self, other: &Self) -> isize {
-        (self.x - other.x).abs() + (self.y - other.y).abs()
-    }
-
+impl Coord2 {
     fn try_from_report(s: &str) -> Result<(Self, Self)> {
         let Some(captures) = REPORT_LINE_RE.captures(s) else {
             return Err(anyhow!("Invalid sensor report {:?}", s));
@@ -40,8 +27,8 @@ impl Coord {
 }
 
 /// Return the range of tiles covered by the given sensor at the given row y
-fn coverage_at_y(sensor: &Coord, beacon: &Coord, y: isize) -> Option<RangeInclusive<isize>> {
-    let distance = sensor.manhattan_distance(beacon);
+fn coverage_at_y(sensor: &Coord2, beacon: &Coord2, y: isize) -> Option<RangeInclusive<isize>> {
+    let distance = sensor.manhattan_distance(*beacon) as isize;
     if ((sensor.y - distance)..=(sensor.y + distance)).contains(&y) {
         let spread = distance - (sensor.y - y).abs();
         Some((sensor.x - spread)..=(sensor.x + spread))
@@ -50,44 +37,36 @@ fn coverage_at_y(sensor: &Coord, beacon: &Coord, y: isize) -> Option<RangeInclus
     }
 }
 
-/// Normalize the given vector of potentially overlapping by merging all adjacent and overlapping
-/// ranges.
-fn normalize_range_set(mut ranges: Vec<RangeInclusive<isize>>) -> Vec<RangeInclusive<isize>> {
-    ranges.sort_by_key(|r| Reverse((*r.start(), *r.end())));
-    let mut normalized: Vec<RangeInclusive<isize>> = Vec::new();
-    while let Some(curr) = ranges.pop() {
-        let Some(prev) = normalized.last_mut() else {
-            normalized.push(curr);
-            continue;
-        };
-        if curr.start() <= prev.end() {
-            let extended_range = (*prev.start())..=((*curr.end()).max(*prev.end()));
-            *prev = extended_range;
-        } else {
-            normalized.push(curr);
-        }
+/// Count the number of tiles where a beacon can't be present for every row in the given range
+fn covered_in_rows(sensors: &[(Coord2, Coord2)], ys: RangeInclusive<isize>) -> usize {
+    let mut num_covered_tiles = 0;
+    for y in ys {
+        let overlapping_coverage = sensors
+            .iter()
+            .filter_map(|(s, b)| coverage_at_y(s, b, y))
+            .collect::<Vec<_>>();
+        let num_beacons_on_row = sensors
+            .iter()
+            .filter_map(|(_, b)| (b.y == y).then_some(b.x))
+            .collect::<HashSet<_>>()
+            .len();
+        let num_covered_tiles_on_row: usize = ranges::merge(overlapping_coverage)
+            .into_iter()
+            .map(Iterator::count)
+            .sum();
+        num_covered_tiles += num_covered_tiles_on_row - num_beacons_on_row;
     }
-    normalized
+    num_covered_tiles
 }
 
-fn part_a(sensors: &[(Coord, Coord)], y: isize) -> usize {
-    let overlapping_coverage = sensors
-        .iter()
-        .filter_map(|(s, b)| coverage_at_y(s, b, y))
-        .collect::<Vec<_>>();
-    let num_beacons_on_row = sensors
-        .iter()
-        .filter_map(|(_, b)| (b.y == y).then_some(b.x))
-        .collect::<HashSet<_>>()
-        .len();
-    let num_covered_tiles: usize = normalize_range_set(overlapping_coverage)
-        .into_iter()
-        .map(Iterator::count)
-        .sum();
-    num_covered_tiles - num_beacons_on_row
+fn part_a(sensors: &[(Coord2, Coord2)], y: isize) -> usize {
+    covered_in_rows(sensors, y..=y)
 }
 
-fn part_b(sensors: &[(Coord, Coord)], limit: isize) -> Result<isize> {
+/// Return every tile within the bounding box (0, 0) to (limit, limit) that isn't covered by any
+/// sensor
+fn find_uncovered(sensors: &[(Coord2, Coord2)], limit: isize) -> Vec<Coord2> {
+    let mut uncovered = Vec::new();
     for y in 0..=limit {
         // Save each sensors coverage of this line as a range in a vector
         let overlapping_coverage = sensors
@@ -95,70 +74,147 @@ fn part_b(sensors: &[(Coord, Coord)], limit: isize) -> Result<isize> {
             .filter_map(|(s, b)| coverage_at_y(s, b, y))
             .collect::<Vec<_>>();
 
-        // Normalize overlapping ranges. If we have a gap within the given bounding box (limit) we
-        // know this is the location for the hidden beacon
-        let mut gaps = normalize_range_set(overlapping_coverage)
-            .into_iter()
-            .skip(1)
-            .map(|r| r.start() - 1);
-        if let Some(x) = gaps.find(|x| (0..=limit).contains(x)) {
-            return Ok(4_000_000 * x + y);
+        // Normalize overlapping ranges. Any gap within the given bounding box (limit) is a
+        // possible location for the hidden beacon
+        let mut x = 0;
+        for range in ranges::merge(overlapping_coverage) {
+            uncovered.extend((x..(*range.start()).min(limit + 1)).map(|x| Coord2::new(x, y)));
+            x = x.max(range.end() + 1);
         }
+        uncovered.extend((x..=limit).map(|x| Coord2::new(x, y)));
     }
-    Err(anyhow!("No solution found"))
+    uncovered
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<isize>)> {
-    let sensors = io::BufReader::new(File::open(path)?)
+/// Return the tuning frequency of the distress beacon at the given position. The frequency of a
+/// beacon near (4_000_000, 4_000_000) needs 45 bits, so it's computed as i64 to not overflow
+/// where isize is 32 bits wide
+fn tuning_frequency(beacon: Coord2) -> i64 {
+    4_000_000 * (beacon.x as i64) + (beacon.y as i64)
+}
+
+/// The hidden beacon that sends out the distress signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DistressBeacon {
+    pub coord: Coord2,
+    pub tuning_frequency: i64,
+}
+
+/// Find the only position within the bounding box (0, 0) to (limit, limit) that no sensor covers
+pub fn find_distress_beacon(sensors: &[(Coord2, Coord2)], limit: isize) -> Result<DistressBeacon> {
+    let uncovered = find_uncovered(sensors, limit);
+    let [coord] = uncovered[..] else {
+        return Err(anyhow!(
+            "Expected exactly one possible beacon location, found {}",
+            uncovered.len()
+        ));
+    };
+    Ok(DistressBeacon {
+        coord,
+        tuning_frequency: tuning_frequency(coord),
+    })
+}
+
+fn part_b(sensors: &[(Coord2, Coord2)], limit: isize) -> Result<i64> {
+    Ok(find_distress_beacon(sensors, limit)?.tuning_frequency)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<i64>)> {
+    let sensors = input
         .lines()
-        .map(|lr| Coord::try_from_report(&lr?))
+        .map(Coord2::try_from_report)
         .collect::<Result<Vec<_>>>()?;
     Ok((
-        part_a(&sensors, 2_000_000),
-        Some(part_b(&sensors, 4_000_000)?),
+        timer.part_a(|| part_a(&sensors, 2_000_000)),
+        Some(timer.part_b(|| part_b(&sensors, 4_000_000))?),
     ))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<i64>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn example_input() -> Vec<(Coord, Coord)> {
+    fn example_input() -> Vec<(Coord2, Coord2)> {
         vec![
-            (Coord::new(2, 18), Coord::new(-2, 15)),
-            (Coord::new(9, 16), Coord::new(10, 16)),
-            (Coord::new(13, 2), Coord::new(15, 3)),
-            (Coord::new(12, 14), Coord::new(10, 16)),
-            (Coord::new(10, 20), Coord::new(10, 16)),
-            (Coord::new(14, 17), Coord::new(10, 16)),
-            (Coord::new(8, 7), Coord::new(2, 10)),
-            (Coord::new(2, 0), Coord::new(2, 10)),
-            (Coord::new(0, 11), Coord::new(2, 10)),
-            (Coord::new(20, 14), Coord::new(25, 17)),
-            (Coord::new(17, 20), Coord::new(21, 22)),
-            (Coord::new(16, 7), Coord::new(15, 3)),
-            (Coord::new(14, 3), Coord::new(15, 3)),
-            (Coord::new(20, 1), Coord::new(15, 3)),
+            (Coord2::new(2, 18), Coord2::new(-2, 15)),
+            (Coord2::new(9, 16), Coord2::new(10, 16)),
+            (Coord2::new(13, 2), Coord2::new(15, 3)),
+            (Coord2::new(12, 14), Coord2::new(10, 16)),
+            (Coord2::new(10, 20), Coord2::new(10, 16)),
+            (Coord2::new(14, 17), Coord2::new(10, 16)),
+            (Coord2::new(8, 7), Coord2::new(2, 10)),
+            (Coord2::new(2, 0), Coord2::new(2, 10)),
+            (Coord2::new(0, 11), Coord2::new(2, 10)),
+            (Coord2::new(20, 14), Coord2::new(25, 17)),
+            (Coord2::new(17, 20), Coord2::new(21, 22)),
+            (Coord2::new(16, 7), Coord2::new(15, 3)),
+            (Coord2::new(14, 3), Coord2::new(15, 3)),
+            (Coord2::new(20, 1), Coord2::new(15, 3)),
         ]
     }
 
     #[test]
     fn test_manhattan_distance() {
-        assert_eq!(Coord::new(8, 7).manhattan_distance(&Coord::new(2, 10)), 9);
+        assert_eq!(Coord2::new(8, 7).manhattan_distance(Coord2::new(2, 10)), 9);
     }
 
     #[test]
-    fn test_normalize_range() {
-        assert_eq!(normalize_range_set(vec![]), vec![]);
-        assert_eq!(normalize_range_set(vec![1..=3, 0..=4]), vec![0..=4]);
-        assert_eq!(normalize_range_set(vec![0..=3, 1..=4]), vec![0..=4]);
-        assert_eq!(normalize_range_set(vec![0..=5, 1..=4]), vec![0..=5]);
-        assert_eq!(normalize_range_set(vec![0..=3, 5..=9]), vec![0..=3, 5..=9]);
+    fn test_example_a() {
+        assert_eq!(part_a(&example_input(), 10), 26);
     }
 
     #[test]
-    fn test_example_a() {
-        assert_eq!(part_a(&example_input(), 10), 26);
+    fn test_covered_in_rows() {
+        let sensors = example_input();
+        assert_eq!(covered_in_rows(&sensors, 10..=10), 26);
+        assert_eq!(
+            covered_in_rows(&sensors, 9..=11),
+            part_a(&sensors, 9) + part_a(&sensors, 10) + part_a(&sensors, 11)
+        );
+        assert_eq!(
+            covered_in_rows(&sensors, 15..=16),
+            part_a(&sensors, 15) + part_a(&sensors, 16)
+        );
+        assert_eq!(covered_in_rows(&sensors, 1_000..=1_001), 0);
+    }
+
+    #[test]
+    fn test_find_uncovered() {
+        assert_eq!(
+            find_uncovered(&example_input(), 20),
+            vec![Coord2::new(14, 11)]
+        );
+
+        // Without sensors every tile is uncovered
+        assert_eq!(find_uncovered(&[], 1).len(), 4);
+    }
+
+    #[test]
+    fn test_tuning_frequency() {
+        assert_eq!(tuning_frequency(Coord2::new(14, 11)), 56_000_011);
+        assert_eq!(
+            tuning_frequency(Coord2::new(4_000_000, 4_000_000)),
+            16_000_004_000_000
+        );
+    }
+
+    #[test]
+    fn test_find_distress_beacon() -> Result<()> {
+        assert_eq!(
+            find_distress_beacon(&example_input(), 20)?,
+            DistressBeacon {
+                coord: Coord2::new(14, 11),
+                tuning_frequency: 56_000_011,
+            }
+        );
+        assert!(find_distress_beacon(&example_input(), 10).is_err());
+        assert!(find_distress_beacon(&[], 1).is_err());
+        Ok(())
     }
 
     #[test]
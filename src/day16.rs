@@ -1,9 +1,10 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -14,17 +15,28 @@ static VALVE_RE: Lazy<Regex> = Lazy::new(|| {
 
 const FIRST_VALVE: &str = "AA";
 
+/// Set of opened valves, where bit i is set if the valve at index i of the cost map is open. This
+/// limits the number of valves with a flow rate to 128
+type ValveSet = u128;
+
 #[derive(Debug)]
-struct ValveSpec {
+pub struct ValveSpec {
     name: String,
     flow_rate: usize,
     leads_to: Vec<String>,
 }
 
+/// Travel costs between the valves that are worth opening, and from the first valve to them
 #[derive(Debug)]
-struct ValveInfo {
-    cost: usize,
-    flow_rate: usize,
+pub struct CostMap {
+    /// Names of all valves with a non-zero flow rate. A valve's index in this list is its bit in
+    /// the set of opened valves
+    names: Vec<String>,
+    flow_rates: Vec<usize>,
+
+    /// Number of minutes it takes to go from one valve to another. The first valve has the index
+    /// after the last valve with a flow rate
+    costs: Vec<Vec<usize>>,
 }
 
 impl FromStr for ValveSpec {
@@ -42,206 +54,478 @@ impl FromStr for ValveSpec {
     }
 }
 
-fn find_shortest_path_lens(
+/// Compute the shortest path lengths between all pairs of valves using Floyd–Warshall
+fn find_all_shortest_path_lens(
     valves: &HashMap<String, ValveSpec>,
-    source: &str,
-) -> Result<HashMap<String, usize>> {
-    let mut to_explore = VecDeque::new();
-    to_explore.push_back(vec![source.to_string()]);
-    let mut visited = HashSet::new();
-
-    let mut shortest_path_lens = HashMap::new();
-    while let Some(path) = to_explore.pop_front() {
-        let source_name = path.last().unwrap();
-        let Some(source) = valves.get(source_name) else {
-            return Err(anyhow!("No such tunnel {:?}", source_name));
-        };
+) -> Result<HashMap<(&str, &str), usize>> {
+    let names = valves.keys().map(String::as_str).collect::<Vec<_>>();
+    let index_of = names
+        .iter()
+        .enumerate()
+        .map(|(i, &name)| (name, i))
+        .collect::<HashMap<_, _>>();
 
-        for next_valve_name in source.leads_to.iter().cloned() {
-            if !visited.insert(next_valve_name.clone()) {
-                continue;
+    let mut dist = vec![vec![usize::MAX; names.len()]; names.len()];
+    for (i, &name) in names.iter().enumerate() {
+        dist[i][i] = 0;
+        for next_valve_name in valves[name].leads_to.iter() {
+            let Some(&j) = index_of.get(next_valve_name.as_str()) else {
+                return Err(anyhow!("No such tunnel {:?}", next_valve_name));
+            };
+            dist[i][j] = 1;
+        }
+    }
+    for k in 0..names.len() {
+        for i in 0..names.len() {
+            for j in 0..names.len() {
+                let via_k = dist[i][k].saturating_add(dist[k][j]);
+                if via_k < dist[i][j] {
+                    dist[i][j] = via_k;
+                }
             }
-            shortest_path_lens.insert(next_valve_name.clone(), path.len());
+        }
+    }
 
-            let mut next_path = path.clone();
-            next_path.push(next_valve_name);
-            to_explore.push_back(next_path);
+    let mut shortest_path_lens = HashMap::new();
+    for (i, &source) in names.iter().enumerate() {
+        for (j, &target) in names.iter().enumerate() {
+            shortest_path_lens.insert((source, target), dist[i][j]);
         }
     }
     Ok(shortest_path_lens)
 }
 
-fn valve_cost_map(
-    valves: &HashMap<String, ValveSpec>,
-) -> Result<HashMap<String, HashMap<String, ValveInfo>>> {
-    let mut cost_map = HashMap::new();
-    for parent_valve in valves.values() {
-        // Skip building a cost map for nodes we'll never open valves at
-        if parent_valve.flow_rate == 0 && parent_valve.name != FIRST_VALVE {
-            continue;
-        }
+fn valve_cost_map(valves: &HashMap<String, ValveSpec>) -> Result<CostMap> {
+    if !valves.contains_key(FIRST_VALVE) {
+        return Err(anyhow!("No such valve {:?}", FIRST_VALVE));
+    }
 
-        let mut local_cost_map = HashMap::new();
-        for (valve_name, cost) in find_shortest_path_lens(valves, &parent_valve.name)? {
-            let Some(valve) = valves.get(&valve_name) else {
-                return Err(anyhow!("No such valve {:?}", valve_name));
-            };
-            if valve.name != FIRST_VALVE && valve.flow_rate == 0 {
-                continue;
-            }
-            local_cost_map.insert(
-                valve_name,
-                ValveInfo {
-                    cost,
-                    flow_rate: valve.flow_rate,
-                },
-            );
+    // We only care about valves that are worth opening, since the others are just a part of the
+    // path between them
+    let mut useful_valves = valves
+        .values()
+        .filter(|v| v.flow_rate > 0)
+        .collect::<Vec<_>>();
+    useful_valves.sort_by(|a, b| a.name.cmp(&b.name));
+    if useful_valves.len() > ValveSet::BITS as usize {
+        return Err(anyhow!(
+            "Too many valves with a flow rate ({}), at most {} are supported",
+            useful_valves.len(),
+            ValveSet::BITS
+        ));
+    }
+
+    let shortest_path_lens = find_all_shortest_path_lens(valves)?;
+    let unreachable = useful_valves
+        .iter()
+        .filter(|v| shortest_path_lens[&(FIRST_VALVE, v.name.as_str())] == usize::MAX)
+        .map(|v| v.name.as_str())
+        .collect::<Vec<_>>();
+    if !unreachable.is_empty() {
+        return Err(anyhow!(
+            "Valves {} can't be reached from {}",
+            unreachable.join(", "),
+            FIRST_VALVE
+        ));
+    }
+
+    let nodes = useful_valves
+        .iter()
+        .map(|v| v.name.as_str())
+        .chain([FIRST_VALVE])
+        .collect::<Vec<_>>();
+    let costs = nodes
+        .iter()
+        .map(|&source| {
+            nodes
+                .iter()
+                .map(|&target| shortest_path_lens[&(source, target)])
+                .collect()
+        })
+        .collect();
+
+    Ok(CostMap {
+        names: useful_valves.iter().map(|v| v.name.clone()).collect(),
+        flow_rates: useful_valves.iter().map(|v| v.flow_rate).collect(),
+        costs,
+    })
+}
+
+impl CostMap {
+    fn first_valve(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Iterate over all valves that can be opened in time, given the current valve and set of
+    /// opened valves. Yields the index of the valve and the time remaining after opening it
+    fn iter_next_valves(
+        &self,
+        curr_valve: usize,
+        time_remaining: usize,
+        opened: ValveSet,
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.names.len())
+            .filter(move |next_valve| opened & (1 << next_valve) == 0)
+            .filter_map(move |next_valve| {
+                let cost = self.costs[curr_valve][next_valve].saturating_add(1);
+                let next_time_remaining = time_remaining.checked_sub(cost)?;
+                (next_time_remaining > 0).then_some((next_valve, next_time_remaining))
+            })
+    }
+
+    fn max_pressure_from(
+        &self,
+        curr_valve: usize,
+        time_remaining: usize,
+        opened: ValveSet,
+        cache: &mut HashMap<(usize, usize, ValveSet), usize>,
+    ) -> usize {
+        if let Some(&pressure) = cache.get(&(curr_valve, time_remaining, opened)) {
+            return pressure;
         }
-        cost_map.insert(parent_valve.name.clone(), local_cost_map);
-    }
-    Ok(cost_map)
-}
-
-fn find_max_pressure(
-    cost_map: &HashMap<String, HashMap<String, ValveInfo>>,
-    time_limit: usize,
-    blacklist: &HashSet<String>,
-) -> Result<usize> {
-    let mut to_visit = Vec::new();
-    to_visit.push((vec![FIRST_VALVE.to_string()], time_limit, 0));
-    let mut max_pressure = 0;
-    while let Some((path, time_remaining, acc_pressure)) = to_visit.pop() {
-        let curr_valve_name = path.last().unwrap();
-        let Some(valve_info) = cost_map.get(curr_valve_name) else {
-            return Err(anyhow!("Unknown valve {:?}", curr_valve_name));
-        };
-        max_pressure = max_pressure.max(acc_pressure);
-
-        // Figure out if this path has potential to beat the best known path. If not, prune it
-        let max_untapped_pressure = valve_info
-            .iter()
-            .filter(|(k, _)| !path.contains(k))
-            .map(|(_, v)| v.flow_rate * time_remaining.saturating_sub(v.cost + 1))
-            .sum::<usize>();
-        if acc_pressure + max_untapped_pressure < max_pressure {
-            continue;
+        let pressure = self
+            .iter_next_valves(curr_valve, time_remaining, opened)
+            .map(|(next_valve, next_time_remaining)| {
+                next_time_remaining * self.flow_rates[next_valve]
+                    + self.max_pressure_from(
+                        next_valve,
+                        next_time_remaining,
+                        opened | (1 << next_valve),
+                        cache,
+                    )
+            })
+            .max()
+            .unwrap_or(0);
+        cache.insert((curr_valve, time_remaining, opened), pressure);
+        pressure
+    }
+
+    /// Return the most pressure that can be released for every set of opened valves that can be
+    /// reached within the time limit
+    fn max_pressure_per_set(&self, time_limit: usize) -> HashMap<ValveSet, usize> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+
+            // Paths that start by opening different valves are independent, so each of them is
+            // searched on its own thread and the results are merged afterwards
+            let first_valves = self
+                .iter_next_valves(self.first_valve(), time_limit, 0)
+                .collect::<Vec<_>>();
+            let mut max_pressures = first_valves
+                .into_par_iter()
+                .map(|(next_valve, next_time_remaining)| {
+                    self.max_pressure_per_set_from(
+                        next_valve,
+                        next_time_remaining,
+                        1 << next_valve,
+                        next_time_remaining * self.flow_rates[next_valve],
+                    )
+                })
+                .reduce(HashMap::new, |mut merged, max_pressures| {
+                    for (opened, pressure) in max_pressures {
+                        let max_pressure = merged.entry(opened).or_insert(0);
+                        *max_pressure = pressure.max(*max_pressure);
+                    }
+                    merged
+                });
+
+            // Not opening any valve at all is also an option
+            max_pressures.entry(0).or_insert(0);
+            max_pressures
         }
 
-        for (next_valve, ValveInfo { cost, flow_rate }) in valve_info {
-            if path.contains(next_valve) || blacklist.contains(next_valve.as_str()) {
-                continue;
+        #[cfg(not(feature = "parallel"))]
+        self.max_pressure_per_set_from(self.first_valve(), time_limit, 0, 0)
+    }
+
+    /// Return the most pressure that can be released for every set of opened valves that can be
+    /// reached from the given valve, when the valves in opened have already released acc_pressure
+    fn max_pressure_per_set_from(
+        &self,
+        valve: usize,
+        time_remaining: usize,
+        opened: ValveSet,
+        acc_pressure: usize,
+    ) -> HashMap<ValveSet, usize> {
+        let mut max_pressures = HashMap::new();
+        let mut to_visit = vec![(valve, time_remaining, opened, acc_pressure)];
+        while let Some((curr_valve, time_remaining, opened, acc_pressure)) = to_visit.pop() {
+            let max_pressure = max_pressures.entry(opened).or_insert(0);
+            *max_pressure = acc_pressure.max(*max_pressure);
+
+            for (next_valve, next_time_remaining) in
+                self.iter_next_valves(curr_valve, time_remaining, opened)
+            {
+                to_visit.push((
+                    next_valve,
+                    next_time_remaining,
+                    opened | (1 << next_valve),
+                    acc_pressure + next_time_remaining * self.flow_rates[next_valve],
+                ));
             }
-            let Some(next_time_remaining) = time_remaining.checked_sub(cost + 1) else {
-                continue;
-            };
-            let mut new_path = path.clone();
-            new_path.push(next_valve.clone());
-            to_visit.push((
-                new_path,
-                next_time_remaining,
-                acc_pressure + next_time_remaining * flow_rate,
-            ));
         }
+        max_pressures
     }
-    Ok(max_pressure)
 }
 
-fn explore_paths(
-    cost_map: &HashMap<String, HashMap<String, ValveInfo>>,
-    time_limit: usize,
-) -> Result<Vec<(usize, HashSet<String>)>> {
-    let mut to_visit = Vec::new();
-    to_visit.push((vec![FIRST_VALVE.to_string()], time_limit, 0));
-    let mut paths = vec![];
-    while let Some((path, time_remaining, acc_pressure)) = to_visit.pop() {
-        let curr_valve_name = path.last().unwrap();
-        let Some(valve_info) = cost_map.get(curr_valve_name) else {
-            return Err(anyhow!("Unknown valve {:?}", curr_valve_name));
+/// Return the most pressure that can be released within the given time limit, along with the
+/// valves to open and the minute each of them is opened
+fn best_plan(cost_map: &CostMap, time_limit: usize) -> (usize, Vec<(String, usize)>) {
+    let mut cache = HashMap::new();
+    let max_pressure =
+        cost_map.max_pressure_from(cost_map.first_valve(), time_limit, 0, &mut cache);
+
+    // Walk the cached results to find which choices lead to the best outcome
+    let mut plan = Vec::new();
+    let mut curr_valve = cost_map.first_valve();
+    let mut time_remaining = time_limit;
+    let mut opened = 0;
+    let mut remaining_pressure = max_pressure;
+    while remaining_pressure > 0 {
+        let Some((next_valve, next_time_remaining)) = cost_map
+            .iter_next_valves(curr_valve, time_remaining, opened)
+            .find(|&(next_valve, next_time_remaining)| {
+                let pressure = next_time_remaining * cost_map.flow_rates[next_valve];
+                let rest = cost_map.max_pressure_from(
+                    next_valve,
+                    next_time_remaining,
+                    opened | (1 << next_valve),
+                    &mut cache,
+                );
+                pressure + rest == remaining_pressure
+            })
+        else {
+            unreachable!("The cached maximum must be reachable by some valve");
         };
-        paths.push((acc_pressure, path.iter().cloned().collect()));
-        for (next_valve, ValveInfo { cost, flow_rate }) in valve_info {
-            if path.contains(next_valve) {
-                continue;
-            }
-            let Some(next_time_remaining) = time_remaining.checked_sub(cost + 1) else {
-                continue;
-            };
-            let mut new_path = path.clone();
-            new_path.push(next_valve.clone());
-            to_visit.push((
-                new_path,
-                next_time_remaining,
-                acc_pressure + next_time_remaining * flow_rate,
-            ));
-        }
+
+        plan.push((
+            cost_map.names[next_valve].clone(),
+            time_limit - next_time_remaining,
+        ));
+        remaining_pressure -= next_time_remaining * cost_map.flow_rates[next_valve];
+        curr_valve = next_valve;
+        time_remaining = next_time_remaining;
+        opened |= 1 << next_valve;
     }
-    Ok(paths)
+    (max_pressure, plan)
 }
 
-fn part_a(cost_map: &HashMap<String, HashMap<String, ValveInfo>>) -> Result<usize> {
-    find_max_pressure(cost_map, 30, &HashSet::new())
+fn part_a(cost_map: &CostMap) -> usize {
+    best_plan(cost_map, 30).0
 }
 
-fn part_b(cost_map: &HashMap<String, HashMap<String, ValveInfo>>) -> Result<usize> {
-    // This only works because the shorter time limit prunes the search space for us. It's still
-    // way slower than what I would like, but my brain is fried at this point.
-    let time_limit = 26;
+/// Return the best combined pressure of the set of valves at the given index and any disjoint set
+/// that comes after it. The sets must be sorted by pressure in descending order
+fn best_disjoint_pair(max_pressures: &[(ValveSet, usize)], i: usize) -> usize {
+    let (our_valves, our_pressure) = max_pressures[i];
+    max_pressures[i..]
+        .iter()
+        .find(|&&(elephant_valves, _)| our_valves & elephant_valves == 0)
+        .map(|&(_, elephant_pressure)| our_pressure + elephant_pressure)
+        .unwrap_or(0)
+}
+
+fn part_b(cost_map: &CostMap) -> usize {
+    // We and the elephant open disjoint sets of valves, so the best combined result is the best
+    // pair of disjoint sets
+    let mut max_pressures = cost_map
+        .max_pressure_per_set(26)
+        .into_iter()
+        .collect::<Vec<_>>();
+    max_pressures.sort_by_key(|&(_, pressure)| Reverse(pressure));
+
+    // Sorting by pressure lets us stop early once no pair can beat the best one we've found
     let mut best_pressure = 0;
-    for (path_pressure, path_valves) in explore_paths(cost_map, time_limit)? {
-        let remainder_pressure = find_max_pressure(cost_map, time_limit, &path_valves)?;
-        best_pressure = best_pressure.max(path_pressure + remainder_pressure);
+    for (i, &(_, our_pressure)) in max_pressures.iter().enumerate() {
+        if 2 * our_pressure <= best_pressure {
+            break;
+        }
+        best_pressure = best_pressure.max(best_disjoint_pair(&max_pressures, i));
     }
-    Ok(best_pressure)
+    best_pressure
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let valves = io::BufReader::new(File::open(path)?)
+/// Render the tunnel network as an undirected Graphviz graph. Every valve is labeled with its
+/// flow rate and every pair of connected valves gets a single edge
+pub fn to_dot(valves: &HashMap<String, ValveSpec>) -> String {
+    let mut names = valves.keys().collect::<Vec<_>>();
+    names.sort();
+    let tunnels = valves
+        .values()
+        .flat_map(|valve| {
+            valve
+                .leads_to
+                .iter()
+                .map(|next| (valve.name.as_str().min(next), valve.name.as_str().max(next)))
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut dot = String::from("graph valves {\n");
+    for name in names {
+        dot += &format!(
+            "    {} [label=\"{}\\nflow rate={}\"];\n",
+            name, name, valves[name].flow_rate
+        );
+    }
+    for (a, b) in tunnels {
+        dot += &format!("    {} -- {};\n", a, b);
+    }
+    dot += "}\n";
+    dot
+}
+
+/// Parse the valve scan into valve specs by name
+pub fn parse_valves(input: &str) -> Result<HashMap<String, ValveSpec>> {
+    input
         .lines()
-        .map(|lr| {
-            let valve: ValveSpec = lr?.parse()?;
+        .map(|line| {
+            let valve: ValveSpec = line.parse()?;
             Ok((valve.name.clone(), valve))
         })
-        .collect::<Result<HashMap<String, ValveSpec>>>()?;
-    let valve_costs = valve_cost_map(&valves)?;
-    Ok((part_a(&valve_costs)?, Some(part_b(&valve_costs)?)))
+        .collect()
+}
+
+/// Parse the valve scan into the travel costs between the valves worth opening
+pub fn parse(input: &str) -> Result<CostMap> {
+    valve_cost_map(&parse_valves(input)?)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let valve_costs = parse(input)?;
+    Ok((
+        timer.part_a(|| part_a(&valve_costs)),
+        Some(timer.part_b(|| part_b(&valve_costs))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn example_valves() -> HashMap<String, HashMap<String, ValveInfo>> {
-        let valves = [
-            "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB",
-            "Valve BB has flow rate=13; tunnels lead to valves CC, AA",
-            "Valve CC has flow rate=2; tunnels lead to valves DD, BB",
-            "Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE",
-            "Valve EE has flow rate=3; tunnels lead to valves FF, DD",
-            "Valve FF has flow rate=0; tunnels lead to valves EE, GG",
-            "Valve GG has flow rate=0; tunnels lead to valves FF, HH",
-            "Valve HH has flow rate=22; tunnel leads to valve GG",
-            "Valve II has flow rate=0; tunnels lead to valves AA, JJ",
-            "Valve JJ has flow rate=21; tunnel leads to valve II",
-        ]
-        .into_iter()
-        .map(|l| {
-            let valve: ValveSpec = l.parse()?;
-            Ok((valve.name.clone(), valve))
-        })
-        .collect::<Result<HashMap<_, _>>>()
-        .unwrap();
-        valve_cost_map(&valves).unwrap()
+    const EXAMPLE: &str = concat!(
+        "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB\n",
+        "Valve BB has flow rate=13; tunnels lead to valves CC, AA\n",
+        "Valve CC has flow rate=2; tunnels lead to valves DD, BB\n",
+        "Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE\n",
+        "Valve EE has flow rate=3; tunnels lead to valves FF, DD\n",
+        "Valve FF has flow rate=0; tunnels lead to valves EE, GG\n",
+        "Valve GG has flow rate=0; tunnels lead to valves FF, HH\n",
+        "Valve HH has flow rate=22; tunnel leads to valve GG\n",
+        "Valve II has flow rate=0; tunnels lead to valves AA, JJ\n",
+        "Valve JJ has flow rate=21; tunnel leads to valve II\n",
+    );
+
+    fn example_valves() -> CostMap {
+        parse(EXAMPLE).unwrap()
     }
 
     #[test]
-    fn test_example_a() -> Result<()> {
-        assert_eq!(part_a(&example_valves())?, 1651);
+    fn test_to_dot() -> Result<()> {
+        let dot = to_dot(&parse_valves(EXAMPLE)?);
+        assert!(dot.starts_with("graph valves {\n"));
+        assert!(dot.ends_with("}\n"));
+        for name in ["AA", "BB", "CC", "DD", "EE", "FF", "GG", "HH", "II", "JJ"] {
+            assert!(dot.contains(&format!("    {} [label=", name)));
+        }
+        assert!(dot.contains("    DD [label=\"DD\\nflow rate=20\"];\n"));
+
+        // Tunnels are listed from both ends but only drawn once
+        assert_eq!(dot.matches("AA -- DD;").count(), 1);
+        assert!(!dot.contains("DD -- AA"));
+        assert_eq!(dot.matches(" -- ").count(), 10);
         Ok(())
     }
 
     #[test]
-    fn test_example_b() -> Result<()> {
-        assert_eq!(part_b(&example_valves())?, 1707);
+    fn test_unreachable_valve() {
+        let input = [
+            EXAMPLE,
+            "Valve ZZ has flow rate=99; tunnel leads to valve YY\n",
+            "Valve YY has flow rate=0; tunnels lead to valves ZZ, XX\n",
+            "Valve XX has flow rate=5; tunnel leads to valve YY\n",
+        ]
+        .concat();
+        assert_eq!(
+            parse(&input).err().unwrap().to_string(),
+            "Valves XX, ZZ can't be reached from AA"
+        );
+    }
+
+    /// Generate a scan where AA leads to a chain of valves with a flow rate of 1 and directly to
+    /// the last valve of the chain, which has a flow rate of 100
+    fn chain_of_valves(len: usize) -> String {
+        let names = (0..len)
+            .map(|i| {
+                let first = char::from(b'B' + (i / 26) as u8);
+                let second = char::from(b'A' + (i % 26) as u8);
+                format!("{}{}", first, second)
+            })
+            .collect::<Vec<_>>();
+        let mut lines = vec![format!(
+            "Valve AA has flow rate=0; tunnels lead to valves {}, {}",
+            names[0],
+            names[len - 1]
+        )];
+        for (i, name) in names.iter().enumerate() {
+            let flow_rate = if i == len - 1 { 100 } else { 1 };
+            let mut leads_to = vec![if i == 0 { "AA" } else { &names[i - 1] }];
+            leads_to.extend(names.get(i + 1).map(String::as_str));
+            lines.push(format!(
+                "Valve {} has flow rate={}; tunnels lead to valves {}",
+                name,
+                flow_rate,
+                leads_to.join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_more_than_64_valves() -> Result<()> {
+        // The last valve uses bit 64 of the set of opened valves
+        let cost_map = parse(&chain_of_valves(65))?;
+        assert_eq!(cost_map.names.len(), 65);
+        assert_eq!(best_plan(&cost_map, 3), (100, vec![("DM".to_string(), 2)]));
+        assert_eq!(cost_map.max_pressure_per_set(3)[&(1 << 64)], 100);
+
+        assert!(parse(&chain_of_valves(128)).is_ok());
+        assert!(parse(&chain_of_valves(129)).is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_example_a() {
+        assert_eq!(part_a(&example_valves()), 1651);
+    }
+
+    #[test]
+    fn test_best_plan() {
+        let (pressure, plan) = best_plan(&example_valves(), 30);
+        assert_eq!(pressure, 1651);
+        assert_eq!(
+            plan,
+            [
+                ("DD", 2),
+                ("BB", 5),
+                ("JJ", 9),
+                ("HH", 17),
+                ("EE", 21),
+                ("CC", 24)
+            ]
+            .into_iter()
+            .map(|(name, minute)| (name.to_string(), minute))
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_example_b() {
+        assert_eq!(part_b(&example_valves()), 1707);
+    }
 }
@@ -1,7 +1,7 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::Read;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,17 +14,14 @@ enum Rock {
 }
 
 #[derive(Debug, Clone, Copy)]
-enum Direction {
+pub enum Direction {
     Left,
     Right,
 }
 
 impl Rock {
-    fn cycle() -> impl Iterator<Item = Self> {
-        [Self::Minus, Self::Plus, Self::L, Self::I, Self::Cube]
-            .into_iter()
-            .cycle()
-    }
+    /// Every rock in the order they fall
+    const ALL: [Self; 5] = [Self::Minus, Self::Plus, Self::L, Self::I, Self::Cube];
 
     fn width(self) -> usize {
         match self {
@@ -46,10 +43,15 @@ impl Rock {
         }
     }
 
-    fn shift_x(self, direction: Direction, x: usize) -> usize {
+    /// Return the x position where the rock spawns in a chamber of the given width
+    fn spawn_x(self, chamber_width: usize) -> usize {
+        2.min(chamber_width.saturating_sub(self.width()))
+    }
+
+    fn shift_x(self, direction: Direction, x: usize, chamber_width: usize) -> usize {
         match direction {
             Direction::Left => x.saturating_sub(1),
-            Direction::Right => (x + 1).min(7 - self.width()),
+            Direction::Right => (x + 1).min(chamber_width.saturating_sub(self.width())),
         }
     }
 
@@ -58,7 +60,7 @@ impl Rock {
             Self::Minus => (0..=3).map(|dx| (x + dx, y)).collect(),
             Self::Plus => (0..=2)
                 .map(|dx| (x + dx, y + 1))
-                .chain(vec![(x + 1, y), (x + 1, y + 2)].into_iter())
+                .chain([(x + 1, y), (x + 1, y + 2)])
                 .collect(),
             Self::L => (0..=2)
                 .map(|dx| (x + dx, y))
@@ -83,40 +85,127 @@ impl Rock {
     }
 }
 
-fn part_a(jet_pattern: &[Direction]) -> usize {
-    let mut tower_height = 0;
-    let mut stationary_rocks = HashSet::new();
-    let mut wind_direction = jet_pattern.iter().cycle().copied();
-    for falling_rock in Rock::cycle().take(2022) {
+const CHAMBER_WIDTH: usize = 7;
+
+/// Return how far below the top of the tower the topmost rock in each column is. A column without
+/// any rocks is as deep as the tower is tall. Two towers with the same profile will behave the
+/// same for all future rocks, as long as no rock falls deeper than the profile
+pub fn top_profile(
+    stationary_rocks: &HashSet<(usize, usize)>,
+    tower_height: usize,
+) -> [usize; CHAMBER_WIDTH] {
+    let mut profile = [tower_height; CHAMBER_WIDTH];
+    for (x, depth) in profile.iter_mut().enumerate() {
+        if let Some(y) = (0..tower_height)
+            .rev()
+            .find(|&y| stationary_rocks.contains(&(x, y)))
+        {
+            *depth = tower_height - y - 1;
+        }
+    }
+    profile
+}
+
+/// State of a simulation that can be paused between rocks and resumed later
+pub struct Chamber<'a> {
+    jet_pattern: &'a [Direction],
+    width: usize,
+    stationary_rocks: HashSet<(usize, usize)>,
+    /// Height of the tower of stationary rocks
+    pub tower_height: usize,
+    /// Index into the jet pattern of the next jet to push a rock
+    pub jet_index: usize,
+    /// Index of the next rock to fall, which is always less than the number of rock shapes
+    pub rock_index: usize,
+}
+
+impl<'a> Chamber<'a> {
+    pub fn new(jet_pattern: &'a [Direction], width: usize) -> Self {
+        Self {
+            jet_pattern,
+            width,
+            stationary_rocks: HashSet::new(),
+            tower_height: 0,
+            jet_index: 0,
+            rock_index: 0,
+        }
+    }
+
+    /// Let the next rock fall until it comes to rest
+    pub fn drop_rock(&mut self) {
+        let falling_rock = Rock::ALL[self.rock_index];
+        self.rock_index = (self.rock_index + 1) % Rock::ALL.len();
+
         // Spawn the rock at the corect position
-        let mut x = 2;
-        let mut y = tower_height + 3;
+        let mut x = falling_rock.spawn_x(self.width);
+        let mut y = self.tower_height + 3;
 
         // Let the rock fall until it is stationary
-        for wind in wind_direction.by_ref() {
+        loop {
+            let wind = self.jet_pattern[self.jet_index];
+            self.jet_index = (self.jet_index + 1) % self.jet_pattern.len();
+
             // Try to move the rock according to the wind. The move doesn't happen if the rock
             // would make the rock collide with a stationary rock
-            let shifted_x = falling_rock.shift_x(wind, x);
-            if !falling_rock.overlaps(&stationary_rocks, shifted_x, y) {
+            let shifted_x = falling_rock.shift_x(wind, x, self.width);
+            if !falling_rock.overlaps(&self.stationary_rocks, shifted_x, y) {
                 x = shifted_x;
             }
 
             // Stop moving the piece if it is resting on a stationary rock
-            if falling_rock.is_supported(&stationary_rocks, x, y) {
-                tower_height = tower_height.max(y + falling_rock.height());
-                stationary_rocks.extend(falling_rock.shape(x, y));
-                break;
+            if falling_rock.is_supported(&self.stationary_rocks, x, y) {
+                self.tower_height = self.tower_height.max(y + falling_rock.height());
+                self.stationary_rocks.extend(falling_rock.shape(x, y));
+                return;
             }
             y -= 1;
         }
     }
-    tower_height
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let mut buf = String::new();
-    File::open(path)?.read_to_string(&mut buf)?;
-    let jet_pattern = buf
+/// Return the height of the tower after the given number of rocks have fallen. Once the top of
+/// the tower repeats the tower grows the same way every cycle, so the cycles are skipped instead of
+/// simulated
+pub fn tower_height(jet_pattern: &[Direction], rocks: usize) -> usize {
+    let mut chamber = Chamber::new(jet_pattern, CHAMBER_WIDTH);
+    let mut seen = HashMap::new();
+    let mut skipped_height = None;
+    let mut num_rocks = 0;
+    while num_rocks < rocks {
+        chamber.drop_rock();
+        num_rocks += 1;
+        if skipped_height.is_some() {
+            continue;
+        }
+
+        let state = (
+            chamber.rock_index,
+            chamber.jet_index,
+            top_profile(&chamber.stationary_rocks, chamber.tower_height),
+        );
+        if let Some((prev_num_rocks, prev_height)) =
+            seen.insert(state, (num_rocks, chamber.tower_height))
+        {
+            let cycle_len = num_rocks - prev_num_rocks;
+            let num_cycles = (rocks - num_rocks) / cycle_len;
+            num_rocks += num_cycles * cycle_len;
+            skipped_height = Some(num_cycles * (chamber.tower_height - prev_height));
+        }
+    }
+    chamber.tower_height + skipped_height.unwrap_or(0)
+}
+
+fn part_a(jet_pattern: &[Direction]) -> usize {
+    tower_height(jet_pattern, 2022)
+}
+
+fn part_b(jet_pattern: &[Direction]) -> usize {
+    tower_height(jet_pattern, 1_000_000_000_000)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let jet_pattern = input
         .trim()
         .chars()
         .map(|c| match c {
@@ -125,7 +214,17 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
             _ => Err(anyhow!("Invalid character in jet pattern {:?}", c)),
         })
         .collect::<Result<Vec<Direction>>>()?;
-    Ok((part_a(&jet_pattern), None))
+    if jet_pattern.is_empty() {
+        return Err(anyhow!("Jet pattern is empty"));
+    }
+    Ok((
+        timer.part_a(|| part_a(&jet_pattern)),
+        Some(timer.part_b(|| part_b(&jet_pattern))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
@@ -135,7 +234,6 @@ mod tests {
     fn example_jet_pattern() -> Vec<Direction> {
         ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>"
             .chars()
-            .into_iter()
             .map(|c| match c {
                 '<' => Direction::Left,
                 '>' => Direction::Right,
@@ -148,4 +246,85 @@ mod tests {
     fn test_example_a() {
         assert_eq!(part_a(&example_jet_pattern()), 3068);
     }
+
+    #[test]
+    fn test_example_b() {
+        assert_eq!(part_b(&example_jet_pattern()), 1_514_285_714_288);
+    }
+
+    #[test]
+    fn test_tower_height() {
+        let jet_pattern = example_jet_pattern();
+        assert_eq!(tower_height(&jet_pattern, 0), 0);
+        assert_eq!(tower_height(&jet_pattern, 1), 1);
+        assert_eq!(tower_height(&jet_pattern, 2022), 3068);
+        assert_eq!(
+            tower_height(&jet_pattern, 1_000_000_000_000),
+            1_514_285_714_288
+        );
+    }
+
+    #[test]
+    fn test_tower_height_matches_simulation() {
+        let jet_pattern = example_jet_pattern();
+        let mut chamber = Chamber::new(&jet_pattern, CHAMBER_WIDTH);
+        for rocks in 1..=500 {
+            chamber.drop_rock();
+            assert_eq!(tower_height(&jet_pattern, rocks), chamber.tower_height);
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let jet_pattern = example_jet_pattern();
+        let mut chamber = Chamber::new(&jet_pattern, CHAMBER_WIDTH);
+
+        // The first rock always gets pushed four times before landing on the floor
+        chamber.drop_rock();
+        assert_eq!(chamber.tower_height, 1);
+        assert_eq!(chamber.jet_index, 4);
+        assert_eq!(chamber.rock_index, 1);
+
+        for _ in 1..2022 {
+            chamber.drop_rock();
+        }
+        assert_eq!(chamber.tower_height, 3068);
+        assert_eq!(chamber.rock_index, 2022 % 5);
+    }
+
+    #[test]
+    fn test_top_profile() {
+        // ..#....
+        // .###..#
+        // ..#...#
+        // ####..#
+        let rocks = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (2, 1),
+            (1, 2),
+            (2, 2),
+            (3, 2),
+            (2, 3),
+            (6, 0),
+            (6, 1),
+            (6, 2),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(top_profile(&rocks, 4), [3, 1, 0, 1, 4, 4, 1]);
+        assert_eq!(top_profile(&HashSet::new(), 0), [0; CHAMBER_WIDTH]);
+    }
+
+    #[test]
+    fn test_narrow_chamber() {
+        let jet_pattern = example_jet_pattern();
+        let mut chamber = Chamber::new(&jet_pattern, 5);
+        for _ in 0..2022 {
+            chamber.drop_rock();
+        }
+        assert_eq!(chamber.tower_height, 3962);
+    }
 }
@@ -1,163 +1,192 @@
-use anyhow::{anyhow, Result};
-use itertools::Itertools;
+use crate::coord::{bounding_box, Coord3};
+use crate::search;
+use crate::Timer;
+use anyhow::Result;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
-use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coord {
-    x: isize,
-    y: isize,
-    z: isize,
+fn part_a(cubes: &HashSet<Coord3>) -> usize {
+    let mut surface_tiles = 0;
+    for cube in cubes.iter() {
+        surface_tiles += 6 - cube.neighbors6().filter(|nc| cubes.contains(nc)).count();
+    }
+    surface_tiles
 }
 
-impl Coord {
-    fn new(x: isize, y: isize, z: isize) -> Self {
-        Self { x, y, z }
+/// Count the exposed faces pointing in each direction. The indices map to the directions +x, -x,
+/// +y, -y, +z and -z, in that order
+pub fn surface_by_orientation(cubes: &HashSet<Coord3>) -> [usize; 6] {
+    let directions = [
+        (1, 0, 0),
+        (-1, 0, 0),
+        (0, 1, 0),
+        (0, -1, 0),
+        (0, 0, 1),
+        (0, 0, -1),
+    ];
+    let mut faces = [0; 6];
+    for cube in cubes.iter() {
+        for (count, (dx, dy, dz)) in faces.iter_mut().zip(directions) {
+            if !cubes.contains(&Coord3::new(cube.x + dx, cube.y + dy, cube.z + dz)) {
+                *count += 1;
+            }
+        }
     }
+    faces
+}
 
-    fn iter_neighbors(self) -> impl Iterator<Item = Self> {
-        [
-            Coord::new(self.x - 1, self.y, self.z),
-            Coord::new(self.x + 1, self.y, self.z),
-            Coord::new(self.x, self.y - 1, self.z),
-            Coord::new(self.x, self.y + 1, self.z),
-            Coord::new(self.x, self.y, self.z - 1),
-            Coord::new(self.x, self.y, self.z + 1),
-        ]
-        .into_iter()
-    }
+/// Dense set of coordinates within a bounding box
+struct Voxels {
+    min: Coord3,
+    max: Coord3,
+    cells: Vec<bool>,
 }
 
-impl FromStr for Coord {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let Some((x_str, rest)) = s.split_once(',') else {
-            return Err(anyhow!("Cube location has no comma ({:?})", s));
-        };
-        let Some((y_str, z_str)) = rest.split_once(',') else {
-            return Err(anyhow!("Cube location has no second comma ({:?})", s));
-        };
-        Ok(Self {
-            x: x_str.parse()?,
-            y: y_str.parse()?,
-            z: z_str.parse()?,
-        })
+impl Voxels {
+    fn new(min: Coord3, max: Coord3) -> Self {
+        let num_cells = (max.x - min.x + 1) * (max.y - min.y + 1) * (max.z - min.z + 1);
+        Self {
+            min,
+            max,
+            cells: vec![false; num_cells.max(0) as usize],
+        }
+    }
+
+    /// Return the index of the given coordinate, or None if it's outside of the bounding box
+    fn index(&self, c: Coord3) -> Option<usize> {
+        if c.x < self.min.x
+            || c.y < self.min.y
+            || c.z < self.min.z
+            || c.x > self.max.x
+            || c.y > self.max.y
+            || c.z > self.max.z
+        {
+            return None;
+        }
+        let width = self.max.x - self.min.x + 1;
+        let depth = self.max.y - self.min.y + 1;
+        let (x, y, z) = (c.x - self.min.x, c.y - self.min.y, c.z - self.min.z);
+        Some(((z * depth + y) * width + x) as usize)
+    }
+
+    fn contains(&self, c: Coord3) -> bool {
+        self.index(c).is_some_and(|i| self.cells[i])
+    }
+
+    /// Add the given coordinate to the set. Returns true if it wasn't already in the set. Panics if
+    /// the coordinate is outside of the bounding box
+    fn insert(&mut self, c: Coord3) -> bool {
+        let i = self
+            .index(c)
+            .expect("Coordinate is outside of the bounding box");
+        !std::mem::replace(&mut self.cells[i], true)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = Coord3> + '_ {
+        (self.min.z..=self.max.z)
+            .flat_map(move |z| {
+                (self.min.y..=self.max.y)
+                    .flat_map(move |y| (self.min.x..=self.max.x).map(move |x| Coord3::new(x, y, z)))
+            })
+            .filter(|&c| self.contains(c))
     }
 }
 
-fn part_a(cubes: &HashSet<Coord>) -> usize {
-    let mut surface_tiles = 0;
-    for cube in cubes.iter() {
-        surface_tiles += 6 - cube
-            .iter_neighbors()
-            .filter(|nc| cubes.contains(nc))
-            .count();
+impl search::Visited<Coord3> for Voxels {
+    fn visit(&mut self, c: Coord3) -> bool {
+        self.insert(c)
     }
-    surface_tiles
 }
 
-fn part_b(cubes: &HashSet<Coord>) -> usize {
-    // Find the bounding box of the set of cubes
-    let (min_x, max_x) = cubes
-        .iter()
-        .copied()
-        .map(|Coord { x, .. }| x)
-        .minmax()
-        .into_option()
-        .unwrap_or((0, 0));
-    let (min_y, max_y) = cubes
-        .iter()
-        .copied()
-        .map(|Coord { y, .. }| y)
-        .minmax()
-        .into_option()
-        .unwrap_or((0, 0));
-    let (min_z, max_z) = cubes
-        .iter()
-        .copied()
-        .map(|Coord { z, .. }| z)
-        .minmax()
-        .into_option()
-        .unwrap_or((0, 0));
-
-    let x_limit = (min_x - 1)..=(max_x + 1);
-    let y_limit = (min_y - 1)..=(max_y + 1);
-    let z_limit = (min_z - 1)..=(max_z + 1);
-
-    // Perform depth first search to find all spaces with water
-    let start = Coord {
-        x: min_x,
-        y: min_y,
-        z: min_z,
-    };
-    let mut to_visit = vec![start];
-    let mut water = [start].into_iter().collect::<HashSet<_>>();
-    while let Some(c) = to_visit.pop() {
-        for nc in c.iter_neighbors() {
-            if !x_limit.contains(&nc.x)
-                || !y_limit.contains(&nc.y)
-                || !z_limit.contains(&nc.z)
-                || cubes.contains(&nc)
-                || water.contains(&nc)
-            {
-                continue;
-            }
-            water.insert(nc);
-            to_visit.push(nc);
-        }
+/// Return the bounding box of the cubes, expanded by one in every direction
+fn padded_bounds(cubes: &HashSet<Coord3>) -> (Coord3, Coord3) {
+    let (min, max) = bounding_box(cubes.iter().copied()).unwrap_or_default();
+    (
+        Coord3::new(min.x - 1, min.y - 1, min.z - 1),
+        Coord3::new(max.x + 1, max.y + 1, max.z + 1),
+    )
+}
+
+/// Return all spaces within the padded bounding box that water can reach from the outside, along
+/// with the lava cubes using the same bounding box
+fn exterior_water(cubes: &HashSet<Coord3>) -> (Voxels, Voxels) {
+    let (min, max) = padded_bounds(cubes);
+    let mut lava = Voxels::new(min, max);
+    for cube in cubes.iter().copied() {
+        lava.insert(cube);
     }
 
+    // Flood fill from the corner of the padded bounding box to find all spaces with water, since
+    // it's guaranteed to be outside of the droplet
+    let mut water = Voxels::new(min, max);
+    search::flood_fill_into(min, &mut water, |&c| {
+        c.neighbors6()
+            .filter(|&nc| lava.index(nc).is_some() && !lava.contains(nc))
+    });
+    (lava, water)
+}
+
+/// Return the number of spaces within the droplet that are neither lava nor reachable by water
+pub fn trapped_air(cubes: &HashSet<Coord3>) -> usize {
+    let (lava, water) = exterior_water(cubes);
+    water.cells.len() - water.iter().count() - lava.iter().count()
+}
+
+fn part_b(cubes: &HashSet<Coord3>) -> usize {
+    let (_, water) = exterior_water(cubes);
     let mut surface_tiles = 0;
     for cube in cubes.iter() {
-        surface_tiles += cube
-            .iter_neighbors()
-            .filter(|nc| water.contains(nc))
-            .count();
+        surface_tiles += cube.neighbors6().filter(|&nc| water.contains(nc)).count();
     }
     surface_tiles
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let cubes = io::BufReader::new(file)
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let cubes = input
         .lines()
-        .map(|lr| lr?.parse())
-        .collect::<Result<HashSet<Coord>>>()?;
-    Ok((part_a(&cubes), Some(part_b(&cubes))))
+        .map(str::parse)
+        .collect::<Result<HashSet<Coord3>>>()?;
+    Ok((
+        timer.part_a(|| part_a(&cubes)),
+        Some(timer.part_b(|| part_b(&cubes))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
 
-    fn small_example() -> HashSet<Coord> {
-        [Coord { x: 1, y: 1, z: 1 }, Coord { x: 2, y: 1, z: 1 }]
+    fn small_example() -> HashSet<Coord3> {
+        [Coord3 { x: 1, y: 1, z: 1 }, Coord3 { x: 2, y: 1, z: 1 }]
             .into_iter()
             .collect()
     }
 
-    fn large_example() -> HashSet<Coord> {
-        [
-            Coord { x: 2, y: 2, z: 2 },
-            Coord { x: 1, y: 2, z: 2 },
-            Coord { x: 3, y: 2, z: 2 },
-            Coord { x: 2, y: 1, z: 2 },
-            Coord { x: 2, y: 3, z: 2 },
-            Coord { x: 2, y: 2, z: 1 },
-            Coord { x: 2, y: 2, z: 3 },
-            Coord { x: 2, y: 2, z: 4 },
-            Coord { x: 2, y: 2, z: 6 },
-            Coord { x: 1, y: 2, z: 5 },
-            Coord { x: 3, y: 2, z: 5 },
-            Coord { x: 2, y: 1, z: 5 },
-            Coord { x: 2, y: 3, z: 5 },
-        ]
-        .into_iter()
-        .collect()
+    const LARGE_EXAMPLE: &[&str] = &[
+        "2,2,2", "1,2,2", "3,2,2", "2,1,2", "2,3,2", "2,2,1", "2,2,3", "2,2,4", "2,2,6", "1,2,5",
+        "3,2,5", "2,1,5", "2,3,5",
+    ];
+
+    fn large_example() -> HashSet<Coord3> {
+        LARGE_EXAMPLE.iter().map(|c| c.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_display_round_trip() -> Result<()> {
+        for &line in LARGE_EXAMPLE {
+            let cube: Coord3 = line.parse()?;
+            assert_eq!(cube.to_string(), line);
+            assert_eq!(cube.to_string().parse::<Coord3>()?, cube);
+        }
+        assert_eq!(Coord3::new(-1, 0, 12).to_string(), "-1,0,12");
+        Ok(())
     }
 
     #[test]
@@ -175,12 +204,50 @@ mod tests {
         assert_eq!(part_b(&large_example()), 58);
     }
 
+    #[test]
+    fn test_trapped_air() {
+        assert_eq!(trapped_air(&small_example()), 0);
+        assert_eq!(trapped_air(&large_example()), 1);
+        assert_eq!(trapped_air(&HashSet::new()), 0);
+    }
+
+    #[test]
+    fn test_surface_by_orientation() {
+        // The two cubes touch along the x axis, hiding one +x and one -x face
+        assert_eq!(surface_by_orientation(&small_example()), [1, 1, 2, 2, 2, 2]);
+
+        let faces = surface_by_orientation(&large_example());
+        assert_eq!(faces.iter().sum::<usize>(), part_a(&large_example()));
+    }
+
+    #[test]
+    fn test_large_solid_droplet() {
+        // A solid ball has no trapped air, so its exterior surface is its whole surface
+        let cubes = (-20..=20)
+            .cartesian_product(-20..=20)
+            .cartesian_product(-20..=20)
+            .map(|((x, y), z)| Coord3::new(x, y, z))
+            .filter(|c| c.x * c.x + c.y * c.y + c.z * c.z <= 400)
+            .collect::<HashSet<_>>();
+        assert_eq!(part_b(&cubes), part_a(&cubes));
+        assert_eq!(trapped_air(&cubes), 0);
+
+        // Hollowing out the ball traps all the air inside of it
+        let hollow = cubes
+            .iter()
+            .copied()
+            .filter(|c| c.x * c.x + c.y * c.y + c.z * c.z > 100)
+            .collect::<HashSet<_>>();
+        assert_eq!(part_b(&hollow), part_a(&cubes));
+        assert_eq!(trapped_air(&hollow), cubes.len() - hollow.len());
+    }
+
     #[test]
     fn test_disjoint_part_a() {
         let cubes = [
-            Coord { x: 1, y: 1, z: 1 },
-            Coord { x: 2, y: 1, z: 1 },
-            Coord { x: 4, y: 1, z: 1 },
+            Coord3 { x: 1, y: 1, z: 1 },
+            Coord3 { x: 2, y: 1, z: 1 },
+            Coord3 { x: 4, y: 1, z: 1 },
         ]
         .into_iter()
         .collect();
@@ -190,10 +257,10 @@ mod tests {
     #[test]
     fn test_two_by_two_cube_part_a() {
         let cubes = [
-            Coord { x: 1, y: 1, z: 1 },
-            Coord { x: 2, y: 1, z: 1 },
-            Coord { x: 1, y: 2, z: 1 },
-            Coord { x: 2, y: 2, z: 1 },
+            Coord3 { x: 1, y: 1, z: 1 },
+            Coord3 { x: 2, y: 1, z: 1 },
+            Coord3 { x: 1, y: 2, z: 1 },
+            Coord3 { x: 2, y: 2, z: 1 },
         ]
         .into_iter()
         .collect();
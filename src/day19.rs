@@ -1,8 +1,8 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -20,7 +20,8 @@ static BLUEPRINT_RE: Lazy<Regex> = Lazy::new(|| {
     .unwrap()
 });
 
-struct Blueprint {
+#[derive(Debug, PartialEq, Eq)]
+pub struct Blueprint {
     id: usize,
     ore_robot_ore_cost: usize,
     clay_robot_ore_cost: usize,
@@ -30,6 +31,68 @@ struct Blueprint {
     geode_robot_obsidian_cost: usize,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RobotKind {
+    Ore,
+    Clay,
+    Obsidian,
+    Geode,
+}
+
+impl RobotKind {
+    /// Every kind of robot, in the order the search should try to build them
+    const ALL: [Self; 4] = [Self::Geode, Self::Obsidian, Self::Clay, Self::Ore];
+}
+
+/// Resources required to build a single robot
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct RobotCost {
+    ore: usize,
+    clay: usize,
+    obsidian: usize,
+}
+
+impl Blueprint {
+    fn cost(&self, kind: RobotKind) -> RobotCost {
+        match kind {
+            RobotKind::Ore => RobotCost {
+                ore: self.ore_robot_ore_cost,
+                ..Default::default()
+            },
+            RobotKind::Clay => RobotCost {
+                ore: self.clay_robot_ore_cost,
+                ..Default::default()
+            },
+            RobotKind::Obsidian => RobotCost {
+                ore: self.obsidian_robot_ore_cost,
+                clay: self.obsidian_robot_clay_cost,
+                ..Default::default()
+            },
+            RobotKind::Geode => RobotCost {
+                ore: self.geode_robot_ore_cost,
+                obsidian: self.geode_robot_obsidian_cost,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Return the most robots of the given kind that are worth building. Since we can only build
+    /// one robot per turn there is no point in producing more of a resource per minute than the
+    /// most expensive robot consumes
+    fn max_useful_robots(&self, kind: RobotKind) -> usize {
+        match kind {
+            RobotKind::Ore => self
+                .ore_robot_ore_cost
+                .max(self.clay_robot_ore_cost)
+                .max(self.obsidian_robot_ore_cost)
+                .max(self.geode_robot_ore_cost),
+            RobotKind::Clay => self.obsidian_robot_clay_cost,
+            RobotKind::Obsidian => self.geode_robot_obsidian_cost,
+            RobotKind::Geode => usize::MAX,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 struct Resources {
     ore_robots: usize,
@@ -52,6 +115,38 @@ impl Resources {
             ..self
         }
     }
+
+    fn robots(&self, kind: RobotKind) -> usize {
+        match kind {
+            RobotKind::Ore => self.ore_robots,
+            RobotKind::Clay => self.clay_robots,
+            RobotKind::Obsidian => self.obsidian_robots,
+            RobotKind::Geode => self.geode_robots,
+        }
+    }
+
+    fn can_afford(&self, cost: &RobotCost) -> bool {
+        self.ore >= cost.ore && self.clay >= cost.clay && self.obsidian >= cost.obsidian
+    }
+
+    /// Pay for a robot of the given kind and add it to the robots. Panics if the robot can't be
+    /// afforded
+    fn build(self, kind: RobotKind, blueprint: &Blueprint) -> Self {
+        let cost = blueprint.cost(kind);
+        let mut r = Self {
+            ore: self.ore - cost.ore,
+            clay: self.clay - cost.clay,
+            obsidian: self.obsidian - cost.obsidian,
+            ..self
+        };
+        match kind {
+            RobotKind::Ore => r.ore_robots += 1,
+            RobotKind::Clay => r.clay_robots += 1,
+            RobotKind::Obsidian => r.obsidian_robots += 1,
+            RobotKind::Geode => r.geode_robots += 1,
+        }
+        r
+    }
 }
 
 impl FromStr for Blueprint {
@@ -73,29 +168,30 @@ impl FromStr for Blueprint {
     }
 }
 
-fn find_max_geodes(blueprint: &Blueprint, time_limit: usize) -> usize {
-    // Since we can only build one robot per turn we limit the number of each robot type to the
-    // maximum resource requirement of that type for any bot. If we allowed more robots to be
-    // built we would produce more than what could be consumed
-    let max_ore_robots = blueprint
-        .ore_robot_ore_cost
-        .max(blueprint.clay_robot_ore_cost)
-        .max(blueprint.obsidian_robot_ore_cost)
-        .max(blueprint.geode_robot_ore_cost);
-    let max_clay_robots = blueprint.obsidian_robot_clay_cost;
-    let max_obsidian_robots = blueprint.geode_robot_obsidian_cost;
-
+/// Return the max number of geodes along with the robots to build to get them. Each build action
+/// is the minute the robot starts being built and what kind of robot it is
+fn best_build_plan(blueprint: &Blueprint, time_limit: usize) -> (usize, Vec<(usize, RobotKind)>) {
+    // Each state on the stack remembers how long the plan was when it was pushed and what robot
+    // it was built by. Since the search is depth first the current plan can be rewound to the
+    // parent state when a state is popped
     let mut build_plans = Vec::new();
     let initial_state = Resources {
         ore_robots: 1,
         ..Default::default()
     };
-    build_plans.push((time_limit, initial_state));
+    build_plans.push((time_limit, initial_state, 0, None));
 
     let mut max_geodes = 0;
-    while let Some((time_remaining, resources)) = build_plans.pop() {
+    let mut plan = Vec::new();
+    let mut best_plan = Vec::new();
+    while let Some((time_remaining, resources, plan_len, built)) = build_plans.pop() {
+        plan.truncate(plan_len);
+        plan.extend(built);
         if time_remaining == 0 {
-            max_geodes = max_geodes.max(resources.geodes);
+            if resources.geodes > max_geodes {
+                max_geodes = resources.geodes;
+                best_plan = plan.clone();
+            }
             continue;
         }
 
@@ -108,64 +204,82 @@ fn find_max_geodes(blueprint: &Blueprint, time_limit: usize) -> usize {
         }
 
         let updated_resources = resources.gather_resources();
-        if resources.ore >= blueprint.geode_robot_ore_cost
-            && resources.obsidian >= blueprint.geode_robot_obsidian_cost
-        {
-            let mut r = updated_resources;
-            r.geode_robots += 1;
-            r.ore -= blueprint.geode_robot_ore_cost;
-            r.obsidian -= blueprint.geode_robot_obsidian_cost;
-            build_plans.push((time_remaining - 1, r));
+        let minute = time_limit - time_remaining + 1;
+        let plan_len = plan.len();
+        for kind in RobotKind::ALL {
+            if resources.robots(kind) < blueprint.max_useful_robots(kind)
+                && resources.can_afford(&blueprint.cost(kind))
+            {
+                build_plans.push((
+                    time_remaining - 1,
+                    updated_resources.build(kind, blueprint),
+                    plan_len,
+                    Some((minute, kind)),
+                ));
+            }
         }
-        if resources.obsidian_robots < max_obsidian_robots
-            && resources.ore >= blueprint.obsidian_robot_ore_cost
-            && resources.clay >= blueprint.obsidian_robot_clay_cost
-        {
-            let mut r = updated_resources;
-            r.obsidian_robots += 1;
-            r.ore -= blueprint.obsidian_robot_ore_cost;
-            r.clay -= blueprint.obsidian_robot_clay_cost;
-            build_plans.push((time_remaining - 1, r));
-        }
-        if resources.clay_robots < max_clay_robots && resources.ore >= blueprint.clay_robot_ore_cost
-        {
-            let mut r = updated_resources;
-            r.clay_robots += 1;
-            r.ore -= blueprint.clay_robot_ore_cost;
-            build_plans.push((time_remaining - 1, r));
-        }
-        if resources.ore_robots < max_ore_robots && resources.ore >= blueprint.ore_robot_ore_cost {
-            let mut r = updated_resources;
-            r.ore_robots += 1;
-            r.ore -= blueprint.ore_robot_ore_cost;
-            build_plans.push((time_remaining - 1, r));
-        }
-        build_plans.push((time_remaining - 1, updated_resources));
+        build_plans.push((time_remaining - 1, updated_resources, plan_len, None));
+    }
+    (max_geodes, best_plan)
+}
+
+/// Return the most geodes the blueprint can open within the time limit
+pub fn find_max_geodes(blueprint: &Blueprint, time_limit: usize) -> usize {
+    best_build_plan(blueprint, time_limit).0
+}
+
+/// Return the max number of geodes for every blueprint within the time limit, in the same order as
+/// the blueprints
+pub fn evaluate(blueprints: &[Blueprint], time_limit: usize) -> Vec<usize> {
+    // Every blueprint is independent so they can be evaluated in any order
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        blueprints
+            .par_iter()
+            .map(|b| find_max_geodes(b, time_limit))
+            .collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        blueprints
+            .iter()
+            .map(|b| find_max_geodes(b, time_limit))
+            .collect()
     }
-    max_geodes
 }
 
 fn part_a(blueprints: &[Blueprint]) -> usize {
     blueprints
         .iter()
-        .map(|b| b.id * find_max_geodes(b, PART_A_TIME_LIMIT))
+        .zip(evaluate(blueprints, PART_A_TIME_LIMIT))
+        .map(|(b, max_geodes)| b.id * max_geodes)
         .sum()
 }
 
 fn part_b(blueprints: &[Blueprint]) -> usize {
-    blueprints
-        .iter()
-        .take(3)
-        .map(|b| find_max_geodes(b, PART_B_TIME_LIMIT))
+    evaluate(&blueprints[..blueprints.len().min(3)], PART_B_TIME_LIMIT)
+        .into_iter()
         .product()
 }
 
+/// Parse one blueprint per line
+pub fn parse(input: &str) -> Result<Vec<Blueprint>> {
+    input.lines().map(str::parse).collect()
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let blueprints = parse(input)?;
+    Ok((
+        timer.part_a(|| part_a(&blueprints)),
+        Some(timer.part_b(|| part_b(&blueprints))),
+    ))
+}
+
 pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let blueprints = io::BufReader::new(File::open(path)?)
-        .lines()
-        .map(|lr| lr?.parse())
-        .collect::<Result<Vec<Blueprint>>>()?;
-    Ok((part_a(&blueprints), Some(part_b(&blueprints))))
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
@@ -192,6 +306,72 @@ mod tests {
         geode_robot_obsidian_cost: 12,
     };
 
+    #[test]
+    fn test_parse() -> Result<()> {
+        let blueprints = parse(concat!(
+            "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian ",
+            "robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.\n",
+            "Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian ",
+            "robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.\n",
+        ))?;
+        assert_eq!(blueprints, [EXAMPLE_BLUEPRINT_1, EXAMPLE_BLUEPRINT_2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(
+            evaluate(&[EXAMPLE_BLUEPRINT_1, EXAMPLE_BLUEPRINT_2], 19),
+            [1, 1]
+        );
+        assert_eq!(evaluate(&[EXAMPLE_BLUEPRINT_1], 0), [0]);
+        assert_eq!(evaluate(&[], 24), []);
+    }
+
+    #[test]
+    fn test_can_afford() {
+        let resources = Resources {
+            ore: 3,
+            clay: 14,
+            ..Default::default()
+        };
+        let obsidian_robot = EXAMPLE_BLUEPRINT_1.cost(RobotKind::Obsidian);
+        assert!(resources.can_afford(&obsidian_robot));
+        assert!(!Resources {
+            ore: 2,
+            ..resources
+        }
+        .can_afford(&obsidian_robot));
+        assert!(!Resources {
+            clay: 13,
+            ..resources
+        }
+        .can_afford(&obsidian_robot));
+
+        // Resources that the robot doesn't need don't make it affordable
+        let geode_robot = EXAMPLE_BLUEPRINT_1.cost(RobotKind::Geode);
+        assert!(!resources.can_afford(&geode_robot));
+        assert!(Resources::default().can_afford(&RobotCost::default()));
+    }
+
+    #[test]
+    fn test_build() {
+        let resources = Resources {
+            ore_robots: 1,
+            ore: 5,
+            clay: 20,
+            ..Default::default()
+        };
+        let built = resources.build(RobotKind::Obsidian, &EXAMPLE_BLUEPRINT_1);
+        assert_eq!((built.ore, built.clay, built.obsidian), (2, 6, 0));
+        assert_eq!(built.obsidian_robots, 1);
+        assert_eq!(built.robots(RobotKind::Ore), 1);
+
+        // Exactly affordable robots leave nothing behind
+        let built = built.build(RobotKind::Clay, &EXAMPLE_BLUEPRINT_1);
+        assert_eq!((built.ore, built.clay_robots), (0, 1));
+    }
+
     #[test]
     fn test_example_a() {
         assert_eq!(part_a(&[EXAMPLE_BLUEPRINT_1, EXAMPLE_BLUEPRINT_2]), 33);
@@ -201,4 +381,37 @@ mod tests {
     fn test_example_b() {
         assert_eq!(part_b(&[EXAMPLE_BLUEPRINT_1, EXAMPLE_BLUEPRINT_2]), 3472);
     }
+
+    #[test]
+    fn test_best_build_plan() {
+        let (max_geodes, plan) = best_build_plan(&EXAMPLE_BLUEPRINT_1, PART_A_TIME_LIMIT);
+        assert_eq!(max_geodes, 9);
+        assert!(plan
+            .iter()
+            .zip(plan.iter().skip(1))
+            .all(|((a, _), (b, _))| a < b));
+
+        // Replaying the plan must produce the same number of geodes
+        let mut geodes = 0;
+        for &(minute, kind) in &plan {
+            if kind == RobotKind::Geode {
+                geodes += PART_A_TIME_LIMIT - minute;
+            }
+        }
+        assert_eq!(geodes, 9);
+    }
+
+    #[test]
+    fn test_matches_serial() {
+        let blueprints = [EXAMPLE_BLUEPRINT_1, EXAMPLE_BLUEPRINT_2];
+        let serial = blueprints
+            .iter()
+            .map(|b| find_max_geodes(b, PART_A_TIME_LIMIT))
+            .collect::<Vec<_>>();
+        assert_eq!(evaluate(&blueprints, PART_A_TIME_LIMIT), serial);
+        assert_eq!(
+            part_a(&blueprints),
+            serial.iter().zip(&blueprints).map(|(g, b)| g * b.id).sum()
+        );
+    }
 }
@@ -1,6 +1,7 @@
+use crate::input::data_lines;
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -62,15 +63,21 @@ fn parse_round(s: &str) -> Result<(char, char)> {
     Ok((their_move, our_move))
 }
 
+/// Return the points for the shape we picked and the points for the outcome of the round
+fn round_breakdown(their_move: Move, our_move: Move) -> (usize, usize) {
+    let outcome_points = if our_move.beats() == their_move {
+        6
+    } else if our_move == their_move {
+        3
+    } else {
+        0
+    };
+    (our_move.value(), outcome_points)
+}
+
 fn score_round(their_move: Move, our_move: Move) -> usize {
-    our_move.value()
-        + if our_move.beats() == their_move {
-            6
-        } else if our_move == their_move {
-            3
-        } else {
-            0
-        }
+    let (shape_value, outcome_points) = round_breakdown(their_move, our_move);
+    shape_value + outcome_points
 }
 
 fn part_a(guide: &[(char, char)]) -> Result<usize> {
@@ -98,20 +105,44 @@ fn part_b(guide: &[(char, char)]) -> Result<usize> {
     Ok(score)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let guide = io::BufReader::new(file)
-        .lines()
-        .map(|lr| parse_round(&lr?))
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let guide = data_lines(input.as_bytes())
+        .map(|line| parse_round(&line?))
         .collect::<Result<Vec<_>, _>>()?;
-    Ok((part_a(&guide)?, Some(part_b(&guide)?)))
+    Ok((
+        timer.part_a(|| part_a(&guide))?,
+        Some(timer.part_b(|| part_b(&guide))?),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static [&'static str] = &["A Y", "B X", "C Z"];
+    const INPUT: &[&str] = &["A Y", "B X", "C Z"];
+
+    #[test]
+    fn test_round_breakdown() {
+        assert_eq!(round_breakdown(Move::Rock, Move::Paper), (2, 6));
+        assert_eq!(round_breakdown(Move::Paper, Move::Rock), (1, 0));
+        assert_eq!(round_breakdown(Move::Scissors, Move::Scissors), (3, 3));
+        assert_eq!(score_round(Move::Rock, Move::Paper), 8);
+    }
+
+    #[test]
+    fn test_comments_and_blank_lines() -> Result<()> {
+        let annotated = "# Strategy guide\nA Y\n\nB X\n# Draw\nC Z\n";
+        assert_eq!(
+            solve(annotated, &mut Timer::new())?,
+            solve(&INPUT.join("\n"), &mut Timer::new())?
+        );
+        Ok(())
+    }
 
     #[test]
     fn test_example_a() -> Result<()> {
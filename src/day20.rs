@@ -1,73 +1,181 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
 
-fn decrypt_grove_coordinate_sum(
+const GROVE_COORDINATE_OFFSETS: [usize; 3] = [1000, 2000, 3000];
+
+/// Mix the file and return the resulting circular list, rotated so that it starts at 0. If there
+/// are several zeros the one that comes first in the file is used, and if there is no 0 the list
+/// starts at the first value of the file
+pub fn decrypt(
     encrypted_file: &[isize],
     num_iterations: usize,
     decryption_key: isize,
-) -> isize {
-    // This produces a shifted version of the example solution, but that doesn't matter since the
-    // list is circular and the answer depends on the position of 0
-    let indexed_values = encrypted_file
+) -> Vec<isize> {
+    let values = encrypted_file
         .iter()
         .copied()
         .map(|v| v * decryption_key)
+        .collect::<Vec<_>>();
+    let len = values.len();
+    let zero_index = values.iter().position(|&v| v == 0).unwrap_or(0);
+
+    // The list of indices into the original values is split into blocks of about sqrt(n)
+    // indices. Finding a value's position, or the position to move it to, skips over whole
+    // blocks, and removing or inserting it only shifts the indices within a single block. That
+    // makes every move O(sqrt(n)) instead of O(n)
+    let block_size = ((len as f64).sqrt() as usize).max(1);
+    let indices = (0..len).collect::<Vec<_>>();
+    let mut blocks = indices
+        .chunks(block_size)
+        .map(<[usize]>::to_vec)
+        .collect::<Vec<_>>();
+    let mut block_of = (0..len).map(|i| i / block_size).collect::<Vec<_>>();
+
+    for _ in 0..num_iterations {
+        for (i, &value) in values.iter().enumerate() {
+            // The value is removed while moving, so moving len - 1 steps brings it back to where
+            // it started
+            let offset = match len {
+                0 | 1 => 0,
+                _ => value.rem_euclid(len as isize - 1) as usize,
+            };
+            if offset == 0 {
+                continue;
+            }
+
+            let block = block_of[i];
+            let index_in_block = blocks[block].iter().position(|&j| j == i).unwrap();
+            let position = blocks[..block].iter().map(Vec::len).sum::<usize>() + index_in_block;
+            blocks[block].remove(index_in_block);
+
+            // Inserting at the end of a block is the same as inserting at the start of the next
+            // one, so the target is always found before running out of blocks
+            let mut target = (position + offset) % (len - 1);
+            let mut target_block = 0;
+            while target > blocks[target_block].len() {
+                target -= blocks[target_block].len();
+                target_block += 1;
+            }
+            blocks[target_block].insert(target, i);
+            block_of[i] = target_block;
+
+            // Split the blocks up again if one of them has grown too large, to keep the moves
+            // O(sqrt(n))
+            if blocks[target_block].len() > 2 * block_size {
+                let indices = blocks.concat();
+                blocks = indices.chunks(block_size).map(<[usize]>::to_vec).collect();
+                for (block, indices) in blocks.iter().enumerate() {
+                    for &j in indices {
+                        block_of[j] = block;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut mixed = blocks.concat();
+    if let Some(start) = mixed.iter().position(|&i| i == zero_index) {
+        mixed.rotate_left(start);
+    }
+    mixed.into_iter().map(|i| values[i]).collect()
+}
+
+/// Mix the file like decrypt, but by removing and inserting values in a single Vec which is O(n)
+/// per move. Kept as a reference to test and benchmark decrypt against
+pub fn decrypt_naive(
+    encrypted_file: &[isize],
+    num_iterations: usize,
+    decryption_key: isize,
+) -> Vec<isize> {
+    let indexed_values = encrypted_file
+        .iter()
+        .map(|v| v * decryption_key)
         .enumerate()
-        .collect::<Vec<(usize, isize)>>();
+        .collect::<Vec<_>>();
+    let zero_index = indexed_values
+        .iter()
+        .position(|&(_, v)| v == 0)
+        .unwrap_or(0);
     let mut reordered_values = indexed_values.clone();
-
     for _ in 0..num_iterations {
-        for (original_index, value) in indexed_values.iter().copied() {
-            let curr_reordered_index = reordered_values
+        for &(original_index, value) in &indexed_values {
+            let curr_index = reordered_values
                 .iter()
-                .position(|x| x == &(original_index, value))
+                .position(|&(i, _)| i == original_index)
                 .unwrap();
-            reordered_values.remove(curr_reordered_index);
-            reordered_values.insert(
-                (curr_reordered_index as isize + value).rem_euclid(reordered_values.len() as isize)
-                    as usize,
-                (original_index, value),
-            );
+            reordered_values.remove(curr_index);
+            if reordered_values.is_empty() {
+                reordered_values.push((original_index, value));
+                continue;
+            }
+            let new_index =
+                (curr_index as isize + value).rem_euclid(reordered_values.len() as isize);
+            reordered_values.insert(new_index as usize, (original_index, value));
         }
     }
-    reordered_values
-        .into_iter()
-        .map(|(_, v)| v)
-        .cycle()
-        .skip_while(|&v| v != 0)
-        .step_by(1000)
-        .skip(1)
-        .take(3)
+    if let Some(start) = reordered_values.iter().position(|&(i, _)| i == zero_index) {
+        reordered_values.rotate_left(start);
+    }
+    reordered_values.into_iter().map(|(_, v)| v).collect()
+}
+
+/// Mix the file and return the sum of the values at the given offsets after 0
+fn decrypt_grove_coordinate_sum(
+    encrypted_file: &[isize],
+    num_iterations: usize,
+    decryption_key: isize,
+    offsets: &[usize],
+) -> isize {
+    let mixed = decrypt(encrypted_file, num_iterations, decryption_key);
+    if mixed.first() != Some(&0) {
+        return 0;
+    }
+    offsets
+        .iter()
+        .map(|offset| mixed[offset % mixed.len()])
         .sum()
 }
 
 fn part_a(encrypted_file: &[isize]) -> isize {
-    decrypt_grove_coordinate_sum(encrypted_file, 1, 1)
+    decrypt_grove_coordinate_sum(encrypted_file, 1, 1, &GROVE_COORDINATE_OFFSETS)
 }
 
 fn part_b(encrypted_file: &[isize]) -> isize {
     let decryption_key = 811589153;
-    decrypt_grove_coordinate_sum(encrypted_file, 10, decryption_key)
+    decrypt_grove_coordinate_sum(
+        encrypted_file,
+        10,
+        decryption_key,
+        &GROVE_COORDINATE_OFFSETS,
+    )
 }
 
-pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
-    let encrypted_file = io::BufReader::new(File::open(path)?)
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(isize, Option<isize>)> {
+    let encrypted_file = input
         .lines()
-        .map(|lr| Ok(lr?.parse()?))
+        .map(|line| Ok(line.parse()?))
         .collect::<Result<Vec<isize>>>()?;
-    if encrypted_file.iter().copied().filter(|&v| v == 0).count() != 1 {
-        return Err(anyhow!("Encrypted must contain exactly one 0"));
+    if !encrypted_file.contains(&0) {
+        return Err(anyhow!("Encrypted file must contain a 0"));
     }
-    Ok((part_a(&encrypted_file), Some(part_b(&encrypted_file))))
+    Ok((
+        timer.part_a(|| part_a(&encrypted_file)),
+        Some(timer.part_b(|| part_b(&encrypted_file))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE_INPUT: &'static [isize] = &[1, 2, -3, 3, -2, 0, 4];
+    const EXAMPLE_INPUT: &[isize] = &[1, 2, -3, 3, -2, 0, 4];
 
     #[test]
     fn test_example_a() {
@@ -78,4 +186,74 @@ mod tests {
     fn test_example_b() {
         assert_eq!(part_b(EXAMPLE_INPUT), 1_623_178_306);
     }
+
+    #[test]
+    fn test_decrypt() {
+        // The example mixes to 1, 2, -3, 4, 0, 3, -2, which starts at 0 when rotated
+        assert_eq!(decrypt(EXAMPLE_INPUT, 1, 1), [0, 3, -2, 1, 2, -3, 4]);
+        assert_eq!(
+            decrypt(EXAMPLE_INPUT, 1, 811589153),
+            [
+                0,
+                -2434767459,
+                3246356612,
+                -1623178306,
+                2434767459,
+                1623178306,
+                811589153
+            ]
+        );
+        assert_eq!(decrypt(&[], 1, 1), []);
+        assert_eq!(decrypt(&[5], 1, 1), [5]);
+
+        // Small files have blocks of a single value, which split up on almost every move
+        for file in [&[1, 2, -3, 3, -2, 0, 4][..], &[0, 1, 0], &[3, 0, -1, 2]] {
+            assert_eq!(decrypt(file, 3, 1), decrypt_naive(file, 3, 1));
+        }
+        assert_eq!(decrypt_naive(EXAMPLE_INPUT, 1, 1), [0, 3, -2, 1, 2, -3, 4]);
+    }
+
+    #[test]
+    fn test_multiple_zeros() -> Result<()> {
+        // The 1 moves past the last 0, so the list only starts with both zeros if it's rotated to
+        // the first one
+        assert_eq!(decrypt(&[0, 1, 0], 1, 1), [0, 0, 1]);
+        assert_eq!(solve("0\n1\n0\n", &mut Timer::new())?.0, 1);
+        assert!(solve("1\n2\n", &mut Timer::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_offsets() {
+        // The example mixes to 1, 2, -3, 4, 0, 3, -2, so the values after 0 are 3, -2 and 1
+        assert_eq!(decrypt_grove_coordinate_sum(EXAMPLE_INPUT, 1, 1, &[1]), 3);
+        assert_eq!(
+            decrypt_grove_coordinate_sum(EXAMPLE_INPUT, 1, 1, &[2, 3]),
+            -1
+        );
+        assert_eq!(decrypt_grove_coordinate_sum(EXAMPLE_INPUT, 1, 1, &[]), 0);
+    }
+
+    #[test]
+    fn test_large_file_matches_naive() {
+        // Generate a pseudo random file of 5000 values with a single 0
+        let mut seed: u64 = 2022;
+        let mut encrypted_file = (0..5000)
+            .map(|_| {
+                seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                ((seed >> 33) % 20_000) as isize - 10_000
+            })
+            .map(|v| if v == 0 { 1 } else { v })
+            .collect::<Vec<_>>();
+        encrypted_file[1234] = 0;
+
+        assert_eq!(
+            decrypt(&encrypted_file, 1, 1),
+            decrypt_naive(&encrypted_file, 1, 1)
+        );
+        assert_eq!(
+            decrypt(&encrypted_file, 2, 811589153),
+            decrypt_naive(&encrypted_file, 2, 811589153)
+        );
+    }
 }
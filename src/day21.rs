@@ -1,13 +1,17 @@
-use anyhow::{anyhow, Result};
+use crate::Timer;
+use anyhow::{anyhow, Context, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::ops::{Add, Mul, Sub};
 use std::path::Path;
+use std::str::FromStr;
 
-static MONKEY_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^([a-z]{4}): (?:(\d+)|([a-z]{4}) ([-+*/]) ([a-z]{4}))$").unwrap());
+static MONKEY_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z]{4}$").unwrap());
+static EXPR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:(\d+)|([a-z]{4}) ([-+*/]) ([a-z]{4}))$").unwrap());
 
 #[derive(Debug, Clone)]
 enum ExprRef {
@@ -41,85 +45,356 @@ enum Monkey {
     },
 }
 
-impl BinOp {
-    fn apply(self, left: &Monkey, right: &Monkey) -> isize {
-        match self {
-            BinOp::Add => left.eval() + right.eval(),
-            BinOp::Sub => left.eval() - right.eval(),
-            BinOp::Mul => left.eval() * right.eval(),
-            BinOp::Div => left.eval() / right.eval(),
+/// Exact fraction that is always kept in its lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ratio {
+    num: isize,
+    den: isize,
+}
+
+impl Ratio {
+    const ZERO: Self = Self::from_integer(0);
+
+    const fn from_integer(value: isize) -> Self {
+        Self { num: value, den: 1 }
+    }
+
+    /// Create a fraction from a non-zero denominator. Use checked_div when dividing by a value
+    /// that may be zero
+    fn new(num: isize, den: isize) -> Self {
+        assert!(den != 0, "attempt to divide by zero");
+        let divisor = gcd(num, den) * den.signum();
+        Self {
+            num: num / divisor,
+            den: den / divisor,
+        }
+    }
+
+    fn to_integer(self) -> Result<isize> {
+        if self.den != 1 {
+            return Err(anyhow!("{}/{} is not an integer", self.num, self.den));
         }
+        Ok(self.num)
     }
+
+    fn checked_div(self, rhs: Self) -> Result<Self> {
+        if rhs.num == 0 {
+            return Err(anyhow!("attempt to divide by zero"));
+        }
+        Ok(Self::new(self.num * rhs.den, self.den * rhs.num))
+    }
+}
+
+fn gcd(a: isize, b: isize) -> isize {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Add for Ratio {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Sub for Ratio {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.den - rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Mul for Ratio {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl BinOp {
+    fn apply(self, left: Ratio, right: Ratio) -> Result<Ratio> {
+        Ok(match self {
+            BinOp::Add => left + right,
+            BinOp::Sub => left - right,
+            BinOp::Mul => left * right,
+            BinOp::Div => left.checked_div(right)?,
+        })
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+        };
+        f.write_str(symbol)
+    }
+}
+
+/// Linear function of an unknown value on the form `a * unknown + b`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Linear {
+    a: Ratio,
+    b: Ratio,
 }
 
 impl Monkey {
-    fn depends_on(&self, monkey: &str) -> bool {
+    fn name(&self) -> &str {
         match self {
-            Monkey::Scalar { name, .. } => name == monkey,
-            Monkey::BinOp {
-                name, left, right, ..
-            } => name == monkey || left.depends_on(monkey) || right.depends_on(monkey),
+            Self::Scalar { name, .. } | Self::BinOp { name, .. } => name,
         }
     }
 
-    fn eval(&self) -> isize {
+    /// Render the monkey as an infix expression where the unknown monkey is kept as its name
+    fn format_infix(&self, unknown: &str, nested: bool) -> String {
         match self {
-            Self::Scalar { value, .. } => *value,
+            Self::Scalar { name, .. } if name == unknown => name.clone(),
+            Self::Scalar { value, .. } => value.to_string(),
             Self::BinOp {
                 op, left, right, ..
-            } => op.apply(left, right),
+            } => {
+                let expr = format!(
+                    "{} {} {}",
+                    left.format_infix(unknown, true),
+                    op,
+                    right.format_infix(unknown, true),
+                );
+                if nested {
+                    format!("({})", expr)
+                } else {
+                    expr
+                }
+            }
         }
     }
+
+    /// Return true if the monkey's value depends on the given monkey. The cache must only be
+    /// shared between calls for the same monkey
+    fn depends_on(&self, monkey: &str, cache: &mut HashMap<String, bool>) -> bool {
+        if let Some(&depends) = cache.get(self.name()) {
+            return depends;
+        }
+        let depends = match self {
+            Monkey::Scalar { name, .. } => name == monkey,
+            Monkey::BinOp {
+                name, left, right, ..
+            } => {
+                name == monkey || left.depends_on(monkey, cache) || right.depends_on(monkey, cache)
+            }
+        };
+        cache.insert(self.name().to_owned(), depends);
+        depends
+    }
+
+    /// Express the monkey's value as a linear function of the unknown monkey's value. Fails if
+    /// the value isn't linear in the unknown
+    fn as_linear(&self, unknown: &str) -> Result<Linear> {
+        let (name, op, left, right) = match self {
+            Self::Scalar { name, .. } if name == unknown => {
+                return Ok(Linear {
+                    a: Ratio::from_integer(1),
+                    b: Ratio::ZERO,
+                })
+            }
+            Self::Scalar { value, .. } => {
+                return Ok(Linear {
+                    a: Ratio::ZERO,
+                    b: Ratio::from_integer(*value),
+                })
+            }
+            Self::BinOp {
+                name,
+                op,
+                left,
+                right,
+            } => (name, op, left, right),
+        };
+        let l = left.as_linear(unknown)?;
+        let r = right.as_linear(unknown)?;
+        Ok(match op {
+            BinOp::Add => Linear {
+                a: l.a + r.a,
+                b: l.b + r.b,
+            },
+            BinOp::Sub => Linear {
+                a: l.a - r.a,
+                b: l.b - r.b,
+            },
+            BinOp::Mul if l.a == Ratio::ZERO => Linear {
+                a: l.b * r.a,
+                b: l.b * r.b,
+            },
+            BinOp::Mul if r.a == Ratio::ZERO => Linear {
+                a: l.a * r.b,
+                b: l.b * r.b,
+            },
+            BinOp::Div if r.a == Ratio::ZERO && r.b != Ratio::ZERO => Linear {
+                a: l.a.checked_div(r.b)?,
+                b: l.b.checked_div(r.b)?,
+            },
+            _ => {
+                return Err(anyhow!(
+                    "Monkey {:?} is not a linear function of {:?}",
+                    name,
+                    unknown,
+                ))
+            }
+        })
+    }
 }
 
-fn parse_monkey(s: &str) -> Result<(String, ExprRef)> {
-    let Some(captures) = MONKEY_RE.captures(s) else {
-        return Err(anyhow!("Invalid monkey {:?}", s));
+/// Evaluate every monkey exactly. Division never truncates, so values may be fractions
+fn eval_all(monkeys: &HashMap<String, ExprRef>) -> Result<HashMap<String, Ratio>> {
+    let mut values = HashMap::new();
+    for name in monkeys.keys() {
+        eval_monkey(monkeys, name, &mut values, &mut HashSet::new())?;
+    }
+    Ok(values)
+}
+
+/// Evaluate the given monkey. Monkeys whose values are already cached are not evaluated again,
+/// so shared monkeys are only computed once
+fn eval_monkey(
+    monkeys: &HashMap<String, ExprRef>,
+    name: &str,
+    cache: &mut HashMap<String, Ratio>,
+    ancestors: &mut HashSet<String>,
+) -> Result<Ratio> {
+    if let Some(&value) = cache.get(name) {
+        return Ok(value);
+    }
+    let Some(expr) = monkeys.get(name) else {
+        return Err(anyhow!("No monkey named {:?}", name));
     };
-    let expr = if captures.get(2).is_some() {
-        ExprRef::Scalar(captures[2].parse()?)
-    } else {
-        let op = match &captures[4] {
+    if !ancestors.insert(name.to_owned()) {
+        return Err(anyhow!("Monkey {:?} depends on its own value", name));
+    }
+
+    let value = match expr {
+        ExprRef::Scalar(value) => Ratio::from_integer(*value),
+        ExprRef::BinOp { op, left, right } => {
+            let left = eval_monkey(monkeys, left, cache, ancestors)?;
+            let right = eval_monkey(monkeys, right, cache, ancestors)?;
+            op.apply(left, right)
+                .with_context(|| format!("Monkey {:?} divides by zero", name))?
+        }
+    };
+    ancestors.remove(name);
+    cache.insert(name.to_owned(), value);
+    Ok(value)
+}
+
+/// Render the equation that part B solves, with both sides of root as infix expressions
+fn format_equation(monkeys: &HashMap<String, ExprRef>, unknown: &str) -> Result<String> {
+    let Monkey::BinOp { left, right, .. } = *into_monkey_ast(monkeys, "root")? else {
+        return Err(anyhow!(
+            "Expected root monkey to depend on a binary operation"
+        ));
+    };
+    Ok(format!(
+        "{} == {}",
+        left.format_infix(unknown, false),
+        right.format_infix(unknown, false),
+    ))
+}
+
+/// Parse the right hand side of a monkey's job, like `5` or `aaaa + bbbb`
+impl FromStr for ExprRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(captures) = EXPR_RE.captures(s) else {
+            return Err(anyhow!("Invalid expression {:?}", s));
+        };
+        if captures.get(1).is_some() {
+            return Ok(Self::Scalar(captures[1].parse()?));
+        }
+        let op = match &captures[3] {
             "+" => BinOp::Add,
             "-" => BinOp::Sub,
             "*" => BinOp::Mul,
             "/" => BinOp::Div,
             _ => unreachable!(), // Unreachable because of the regex
         };
-        let left = captures[3].to_string();
-        let right = captures[5].to_string();
-        ExprRef::BinOp { op, left, right }
+        let left = captures[2].to_string();
+        let right = captures[4].to_string();
+        Ok(Self::BinOp { op, left, right })
+    }
+}
+
+fn parse_monkey(s: &str) -> Result<(String, ExprRef)> {
+    let Some((name, expr)) = s.split_once(": ") else {
+        return Err(anyhow!("Invalid monkey {:?}", s));
     };
-    Ok((captures[1].to_string(), expr))
+    if !MONKEY_NAME_RE.is_match(name) {
+        return Err(anyhow!("Invalid monkey name {:?}", name));
+    }
+    Ok((name.to_string(), expr.parse()?))
 }
 
 fn into_monkey_ast<T: Into<String>>(
-    monkeys: &mut HashMap<String, ExprRef>,
+    monkeys: &HashMap<String, ExprRef>,
     root: T,
 ) -> Result<Box<Monkey>> {
-    // We remove nodes as a validation step to ensure that no monkey's value is used more than once
-    let name = root.into();
-    let Some(expr) = monkeys.remove(&name) else {
-        return Err(anyhow!("No monkey named root, or its value was already used by another monkey"));
+    build_monkey_ast(monkeys, root.into(), &mut HashSet::new())
+}
+
+fn build_monkey_ast(
+    monkeys: &HashMap<String, ExprRef>,
+    name: String,
+    ancestors: &mut HashSet<String>,
+) -> Result<Box<Monkey>> {
+    // A monkey's value may be used by several monkeys, but a monkey must never depend on itself
+    let Some(expr) = monkeys.get(&name) else {
+        return Err(anyhow!("No monkey named {:?}", name));
     };
+    if !ancestors.insert(name.clone()) {
+        return Err(anyhow!("Monkey {:?} depends on its own value", name));
+    }
 
-    Ok(Box::new(match expr {
-        ExprRef::Scalar(value) => Monkey::Scalar { name, value },
+    let monkey = match expr {
+        ExprRef::Scalar(value) => Monkey::Scalar {
+            name: name.clone(),
+            value: *value,
+        },
         ExprRef::BinOp { op, left, right } => Monkey::BinOp {
-            name,
-            op,
-            left: into_monkey_ast(monkeys, left)?,
-            right: into_monkey_ast(monkeys, right)?,
+            name: name.clone(),
+            op: *op,
+            left: build_monkey_ast(monkeys, left.clone(), ancestors)?,
+            right: build_monkey_ast(monkeys, right.clone(), ancestors)?,
         },
-    }))
+    };
+    ancestors.remove(&name);
+    Ok(Box::new(monkey))
 }
 
-fn part_b(root_monkey: Monkey) -> Result<isize> {
-    // This solution relies on the assumption that each monkey's value is only used once. We use
-    // this to treat each monkey as an equation and substitute every monkey into the root one and
-    // solve for "humn"
-    let Monkey::BinOp { name, left, right, .. } = root_monkey else {
-        return Err(anyhow!("Expected root monkey to depend on a binary operation"));
+fn part_a(values: &HashMap<String, Ratio>) -> Result<isize> {
+    let Some(root) = values.get("root") else {
+        return Err(anyhow!("No monkey named root"));
+    };
+    root.to_integer()
+}
+
+fn part_b(monkeys: &HashMap<String, ExprRef>, values: &HashMap<String, Ratio>) -> Result<isize> {
+    // We treat each monkey as an equation and substitute every monkey into the root one and solve
+    // for "humn". As long as only one side of an operation depends on humn we can undo the
+    // operation on the static side
+    let Monkey::BinOp {
+        name, left, right, ..
+    } = *into_monkey_ast(monkeys, "root")?
+    else {
+        return Err(anyhow!(
+            "Expected root monkey to depend on a binary operation"
+        ));
     };
     let mut monkey = &Monkey::BinOp {
         op: BinOp::Sub,
@@ -127,7 +402,8 @@ fn part_b(root_monkey: Monkey) -> Result<isize> {
         left,
         right,
     };
-    let mut static_value = 0;
+    let mut static_value = Ratio::ZERO;
+    let mut humn_dependents = HashMap::new();
     loop {
         let (name, op, left, right) = match monkey {
             Monkey::BinOp {
@@ -139,27 +415,60 @@ fn part_b(root_monkey: Monkey) -> Result<isize> {
             } => (name, op, left, right),
             Monkey::Scalar { name, .. } => {
                 if name == "humn" {
-                    return Ok(static_value);
+                    return static_value
+                        .to_integer()
+                        .context("humn must yell a fraction to pass the equality test");
                 } else {
                     return Err(anyhow!("Expected monkey to depend on a binary operation"));
                 }
             }
         };
 
-        if left.depends_on("humn") {
+        let left_depends = left.depends_on("humn", &mut humn_dependents);
+        let right_depends = right.depends_on("humn", &mut humn_dependents);
+        if left_depends && right_depends {
+            // When humn appears on both sides we can no longer undo one operation at a time.
+            // Instead we isolate humn algebraically in the remaining equation
+            let equation = format_equation(monkeys, "humn")?;
+            let Linear { a, b } = monkey
+                .as_linear("humn")
+                .with_context(|| format!("Failed to solve {}", equation))?;
+            if a == Ratio::ZERO {
+                return Err(anyhow!("{} has no unique solution for humn", equation));
+            }
+            return (static_value - b).checked_div(a)?.to_integer();
+        } else if left_depends {
+            if matches!(op, BinOp::Mul) && values[right.name()] == Ratio::ZERO {
+                return Err(anyhow!(
+                    "Monkey {:?} multiplies by zero, so humn has no unique value",
+                    name
+                ));
+            }
             match op {
-                BinOp::Add => static_value -= right.eval(),
-                BinOp::Sub => static_value += right.eval(),
-                BinOp::Mul => static_value /= right.eval(),
-                BinOp::Div => static_value *= right.eval(),
+                BinOp::Add => static_value = static_value - values[right.name()],
+                BinOp::Sub => static_value = static_value + values[right.name()],
+                BinOp::Mul => static_value = static_value.checked_div(values[right.name()])?,
+                BinOp::Div => static_value = static_value * values[right.name()],
             }
             monkey = left;
-        } else if right.depends_on("humn") {
+        } else if right_depends {
+            let divides_by_zero = match op {
+                BinOp::Mul => values[left.name()] == Ratio::ZERO,
+                BinOp::Div => static_value == Ratio::ZERO,
+                _ => false,
+            };
+            if divides_by_zero {
+                return Err(anyhow!(
+                    "Can't undo {:?} in monkey {:?} since it would divide by zero",
+                    op,
+                    name
+                ));
+            }
             match op {
-                BinOp::Add => static_value -= left.eval(),
-                BinOp::Sub => static_value = left.eval() - static_value,
-                BinOp::Mul => static_value /= left.eval(),
-                BinOp::Div => static_value = left.eval() / static_value,
+                BinOp::Add => static_value = static_value - values[left.name()],
+                BinOp::Sub => static_value = values[left.name()] - static_value,
+                BinOp::Mul => static_value = static_value.checked_div(values[left.name()])?,
+                BinOp::Div => static_value = values[left.name()].checked_div(static_value)?,
             }
             monkey = right;
         } else {
@@ -171,22 +480,41 @@ fn part_b(root_monkey: Monkey) -> Result<isize> {
     }
 }
 
-pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
-    let file = File::open(path)?;
-    let mut monkeys = io::BufReader::new(file)
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(isize, Option<isize>)> {
+    let monkeys = input
         .lines()
-        .map(|lr| parse_monkey(&lr?))
+        .map(parse_monkey)
         .collect::<Result<HashMap<_, _>>>()?;
-    let root_monkey = into_monkey_ast(&mut monkeys, "root")?;
-    Ok((root_monkey.eval(), Some(part_b(*root_monkey)?)))
+    let values = eval_all(&monkeys)?;
+    Ok((
+        timer.part_a(|| part_a(&values))?,
+        Some(timer.part_b(|| part_b(&monkeys, &values))?),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn example_monkeys() -> Monkey {
-        let mut monkeys = [
+    fn parse_monkeys(lines: &[&str]) -> HashMap<String, ExprRef> {
+        lines
+            .iter()
+            .map(|l| parse_monkey(l))
+            .collect::<Result<HashMap<_, _>>>()
+            .unwrap()
+    }
+
+    fn solve_part_b(monkeys: &HashMap<String, ExprRef>) -> Result<isize> {
+        part_b(monkeys, &eval_all(monkeys)?)
+    }
+
+    fn example_monkeys() -> HashMap<String, ExprRef> {
+        parse_monkeys(&[
             "root: pppw + sjmn",
             "dbpl: 5",
             "cczh: sllz + lgvd",
@@ -202,23 +530,227 @@ mod tests {
             "lgvd: ljgn * ptdq",
             "drzm: hmdt - zczc",
             "hmdt: 32",
-        ]
-        .into_iter()
-        .map(|l| parse_monkey(l))
-        .collect::<Result<HashMap<_, _>>>()
-        .unwrap();
-        *into_monkey_ast(&mut monkeys, "root").unwrap()
+        ])
+    }
+
+    #[test]
+    fn test_parse_expr() -> Result<()> {
+        assert!(matches!("5".parse()?, ExprRef::Scalar(5)));
+        for (s, expected_op) in [
+            ("aaaa + bbbb", BinOp::Add),
+            ("aaaa - bbbb", BinOp::Sub),
+            ("aaaa * bbbb", BinOp::Mul),
+            ("aaaa / bbbb", BinOp::Div),
+        ] {
+            let ExprRef::BinOp { op, left, right } = s.parse()? else {
+                panic!("Expected {:?} to be a binary operation", s);
+            };
+            assert_eq!(op.to_string(), expected_op.to_string());
+            assert_eq!((left.as_str(), right.as_str()), ("aaaa", "bbbb"));
+        }
+        assert!("aaaa % bbbb".parse::<ExprRef>().is_err());
+        assert!("-5".parse::<ExprRef>().is_err());
+        assert!("root: 5".parse::<ExprRef>().is_err());
+
+        assert!(parse_monkey("root: aaaa + bbbb").is_ok());
+        assert!(parse_monkey("ROOT: 5").is_err());
+        assert!(parse_monkey("root 5").is_err());
+        Ok(())
     }
 
     #[test]
     fn test_part_a() -> Result<()> {
-        assert_eq!(example_monkeys().eval(), 152);
+        assert_eq!(part_a(&eval_all(&example_monkeys())?)?, 152);
         Ok(())
     }
 
     #[test]
     fn test_part_b() -> Result<()> {
-        assert_eq!(part_b(example_monkeys())?, 301);
+        assert_eq!(solve_part_b(&example_monkeys())?, 301);
         Ok(())
     }
+
+    #[test]
+    fn test_part_b_humn_on_both_sides() -> Result<()> {
+        // humn * 3 == humn + 10
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: humn * cccc",
+            "bbbb: humn + dddd",
+            "cccc: 3",
+            "dddd: 10",
+            "humn: 1",
+        ]);
+        assert_eq!(solve_part_b(&monkeys)?, 5);
+
+        // ((humn * 3 + humn) / 2) - 1 == 13
+        let monkeys = parse_monkeys(&[
+            "root: aaaa - bbbb",
+            "aaaa: eeee - ffff",
+            "eeee: gggg / hhhh",
+            "gggg: iiii + humn",
+            "iiii: humn * cccc",
+            "bbbb: 13",
+            "cccc: 3",
+            "ffff: 1",
+            "hhhh: 2",
+            "humn: 1",
+        ]);
+        assert_eq!(solve_part_b(&monkeys)?, 7);
+        Ok(())
+    }
+
+    #[test]
+    fn test_inexact_division() -> Result<()> {
+        // (7 / 2) * 2 would be 6 with truncating division
+        let monkeys = parse_monkeys(&[
+            "root: aaaa * bbbb",
+            "aaaa: cccc / bbbb",
+            "bbbb: 2",
+            "cccc: 7",
+        ]);
+        assert_eq!(part_a(&eval_all(&monkeys)?)?, 7);
+
+        let monkeys = parse_monkeys(&["root: aaaa / bbbb", "aaaa: 7", "bbbb: 2"]);
+        assert_eq!(eval_all(&monkeys)?["root"], Ratio::new(7, 2));
+        assert!(part_a(&eval_all(&monkeys)?).is_err());
+
+        // humn / 4 == 3 / 2 has the integer solution 6
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: humn / cccc",
+            "bbbb: dddd / eeee",
+            "cccc: 4",
+            "dddd: 3",
+            "eeee: 2",
+            "humn: 1",
+        ]);
+        assert_eq!(solve_part_b(&monkeys)?, 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: cccc / dddd",
+            "bbbb: 2",
+            "cccc: 7",
+            "dddd: bbbb - bbbb",
+        ]);
+        let err = eval_all(&monkeys).err().unwrap();
+        assert_eq!(err.to_string(), "Monkey \"aaaa\" divides by zero");
+        assert_eq!(err.root_cause().to_string(), "attempt to divide by zero");
+        assert!(solve("root: aaaa / bbbb\naaaa: 1\nbbbb: 0", &mut Timer::new()).is_err());
+    }
+
+    #[test]
+    fn test_part_b_without_integer_solution() -> Result<()> {
+        // humn * 2 == 7 only has the solution 7 / 2
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: humn * cccc",
+            "bbbb: 7",
+            "cccc: 2",
+            "humn: 1",
+        ]);
+        let err = solve_part_b(&monkeys).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "humn must yell a fraction to pass the equality test"
+        );
+        assert_eq!(err.root_cause().to_string(), "7/2 is not an integer");
+
+        // humn * 0 == 0 holds for any humn
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: humn * cccc",
+            "bbbb: 0",
+            "cccc: 0",
+            "humn: 1",
+        ]);
+        assert!(solve_part_b(&monkeys).is_err());
+
+        // 6 / humn == 0 has no solution
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: cccc / humn",
+            "bbbb: 0",
+            "cccc: 6",
+            "humn: 1",
+        ]);
+        assert!(solve_part_b(&monkeys).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_equation() -> Result<()> {
+        assert_eq!(
+            format_equation(&example_monkeys(), "humn")?,
+            "(4 + (2 * (humn - 3))) / 4 == (32 - 2) * 5",
+        );
+        assert_eq!(
+            format_equation(&example_monkeys(), "dbpl")?,
+            "(4 + (2 * (5 - 3))) / 4 == (32 - 2) * dbpl",
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_diamond() -> Result<()> {
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: shrd * cccc",
+            "bbbb: shrd + cccc",
+            "shrd: dddd - eeee",
+            "cccc: 2",
+            "dddd: 9",
+            "eeee: 4",
+        ]);
+        let values = eval_all(&monkeys)?;
+        assert_eq!(values.len(), monkeys.len());
+        assert_eq!(values["shrd"], Ratio::from_integer(5));
+        assert_eq!(values["root"], Ratio::from_integer(17));
+
+        // A cached monkey is never evaluated again, so a bogus cached value must show up in both
+        // branches of the diamond
+        let mut cache = HashMap::new();
+        cache.insert("shrd".to_owned(), Ratio::from_integer(10));
+        let root = eval_monkey(&monkeys, "root", &mut cache, &mut HashSet::new())?;
+        assert_eq!(root, Ratio::from_integer(32));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratio() -> Result<()> {
+        assert_eq!(Ratio::new(4, -6), Ratio::new(-2, 3));
+        assert_eq!(Ratio::new(1, 2) + Ratio::new(1, 3), Ratio::new(5, 6));
+        assert_eq!(Ratio::new(1, 2) - Ratio::new(1, 2), Ratio::ZERO);
+        assert_eq!(Ratio::new(2, 3) * Ratio::new(3, 4), Ratio::new(1, 2));
+        assert_eq!(
+            Ratio::new(2, 3).checked_div(Ratio::new(4, 3))?,
+            Ratio::new(1, 2)
+        );
+        assert!(Ratio::new(2, 3).checked_div(Ratio::ZERO).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_part_b_non_linear() {
+        let monkeys = parse_monkeys(&[
+            "root: aaaa + bbbb",
+            "aaaa: humn * humn",
+            "bbbb: 4",
+            "humn: 1",
+        ]);
+        let err = solve_part_b(&monkeys).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to solve humn * humn == 4");
+    }
+
+    #[test]
+    fn test_cyclic_monkeys() {
+        let monkeys = parse_monkeys(&["root: aaaa + bbbb", "aaaa: root * bbbb", "bbbb: 4"]);
+        assert!(into_monkey_ast(&monkeys, "root").is_err());
+        assert!(eval_all(&monkeys).is_err());
+    }
 }
@@ -1,16 +1,10 @@
+use crate::coord::{bounding_box, Coord2};
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::Read;
+use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coord {
-    x: isize,
-    y: isize,
-}
-
 #[derive(Debug, Clone, Copy)]
 enum Direction {
     North,
@@ -19,23 +13,17 @@ enum Direction {
     East,
 }
 
-impl Coord {
-    fn new(x: isize, y: isize) -> Self {
-        Self { x, y }
-    }
-}
-
 impl Direction {
-    fn try_move(self, elf: Coord, elves: &HashSet<Coord>) -> Option<Coord> {
+    fn try_move(self, elf: Coord2, elves: &HashSet<Coord2>) -> Option<Coord2> {
         let (delta_left, delta_front, delta_right) = match self {
-            Self::North => (Coord::new(-1, -1), Coord::new(0, -1), Coord::new(1, -1)),
-            Self::South => (Coord::new(1, 1), Coord::new(0, 1), Coord::new(-1, 1)),
-            Self::West => (Coord::new(-1, -1), Coord::new(-1, 0), Coord::new(-1, 1)),
-            Self::East => (Coord::new(1, -1), Coord::new(1, 0), Coord::new(1, 1)),
+            Self::North => (Coord2::new(-1, -1), Coord2::new(0, -1), Coord2::new(1, -1)),
+            Self::South => (Coord2::new(1, 1), Coord2::new(0, 1), Coord2::new(-1, 1)),
+            Self::West => (Coord2::new(-1, -1), Coord2::new(-1, 0), Coord2::new(-1, 1)),
+            Self::East => (Coord2::new(1, -1), Coord2::new(1, 0), Coord2::new(1, 1)),
         };
-        let left = Coord::new(elf.x + delta_left.x, elf.y + delta_left.y);
-        let front = Coord::new(elf.x + delta_front.x, elf.y + delta_front.y);
-        let right = Coord::new(elf.x + delta_right.x, elf.y + delta_right.y);
+        let left = elf + delta_left;
+        let front = elf + delta_front;
+        let right = elf + delta_right;
         if !elves.contains(&left) && !elves.contains(&front) && !elves.contains(&right) {
             Some(front)
         } else {
@@ -60,7 +48,8 @@ impl Iterator for Direction {
     }
 }
 
-fn find_elves(s: &str) -> Result<HashSet<Coord>> {
+/// Parse the scan into the set of positions occupied by elves
+pub fn parse(s: &str) -> Result<HashSet<Coord2>> {
     let mut map = HashSet::new();
     for (y, line) in s.lines().enumerate() {
         let y: isize = y.try_into()?;
@@ -69,7 +58,7 @@ fn find_elves(s: &str) -> Result<HashSet<Coord>> {
             match c {
                 '.' => {}
                 '#' => {
-                    map.insert(Coord { x, y });
+                    map.insert(Coord2::new(x, y));
                 }
                 _ => return Err(anyhow!("Unexpected character {:?} in map", c)),
             }
@@ -78,74 +67,152 @@ fn find_elves(s: &str) -> Result<HashSet<Coord>> {
     Ok(map)
 }
 
-fn process_round(elves: HashSet<Coord>, starting_direction: Direction) -> HashSet<Coord> {
-    let mut cell_wantedness: HashMap<Coord, usize> = HashMap::new();
+fn has_neighbors(elf: Coord2, elves: &HashSet<Coord2>) -> bool {
+    elf.neighbors8().any(|n| elves.contains(&n))
+}
+
+/// Move every elf that wants to and is allowed to. Elves that aren't in crowded had no neighbors
+/// last round and nobody has moved next to them since, so they won't move. Return the elves that
+/// may want to move next round, which is empty when no elf moved, along with how many elves moved
+fn process_round(
+    elves: &mut HashSet<Coord2>,
+    crowded: &HashSet<Coord2>,
+    starting_direction: Direction,
+) -> (HashSet<Coord2>, usize) {
+    let mut cell_wantedness: HashMap<Coord2, usize> = HashMap::new();
     let mut wanted_moves = Vec::new();
+    let mut next_crowded = HashSet::new();
 
-    for elf in elves.iter().copied() {
-        let mut target = elf;
-        let should_move = !Direction::North
+    for elf in crowded.iter().copied() {
+        if !has_neighbors(elf, elves) {
+            continue;
+        }
+        next_crowded.insert(elf);
+        if let Some(target) = starting_direction
             .take(4)
-            .all(|d| d.try_move(elf, &elves).is_some());
-        if should_move {
-            for dir in starting_direction.take(4) {
-                if let Some(next_position) = dir.try_move(elf, &elves) {
-                    target = next_position;
-                    break;
-                }
-            }
+            .find_map(|dir| dir.try_move(elf, elves))
+        {
+            *cell_wantedness.entry(target).or_default() += 1;
+            wanted_moves.push((elf, target));
         }
-
-        *cell_wantedness.entry(target).or_default() += 1;
-        wanted_moves.push((elf, target));
     }
-    wanted_moves
+
+    let moves = wanted_moves
         .into_iter()
-        .map(|(curr, wanted)| {
-            if cell_wantedness.get(&wanted).copied().unwrap() == 1 {
-                wanted
-            } else {
-                curr
-            }
-        })
-        .collect()
-}
+        .filter(|(_, target)| cell_wantedness[target] == 1)
+        .collect::<Vec<_>>();
+    if moves.is_empty() {
+        return (HashSet::new(), 0);
+    }
 
-fn part_a(mut elves: HashSet<Coord>) -> isize {
-    for (starting_direction, _) in Direction::North.zip(0..10) {
-        elves = process_round(elves, starting_direction);
+    // Elves only move to empty tiles, so no elf can move to a tile that is vacated this round
+    for (curr, target) in moves.iter().copied() {
+        elves.remove(&curr);
+        elves.insert(target);
+        next_crowded.remove(&curr);
+        next_crowded.insert(target);
     }
 
-    // Find bounding box and calculate the number of empty ground tiles
-    let (min_x, max_x) = elves.iter().map(|c| c.x).minmax().into_option().unwrap();
-    let (min_y, max_y) = elves.iter().map(|c| c.y).minmax().into_option().unwrap();
-    (max_x - min_x + 1) * (max_y - min_y + 1) - (elves.len() as isize)
+    // Any elf next to an elf that moved may no longer be alone
+    for (_, target) in moves.iter() {
+        next_crowded.extend(target.neighbors8().filter(|n| elves.contains(n)));
+    }
+    (next_crowded, moves.len())
 }
 
-fn part_b(mut elves: HashSet<Coord>) -> usize {
-    for (starting_direction, round) in Direction::North.zip(1..) {
-        let next_elves = process_round(elves.clone(), starting_direction);
-        if elves == next_elves {
-            return round;
+/// Run up to the given number of rounds and return the final elf positions together with the
+/// first round in which no elf moved, or the number of rounds if the elves never stopped
+pub fn simulate(mut elves: HashSet<Coord2>, rounds: usize) -> (HashSet<Coord2>, usize) {
+    // Every elf may want to move in the first round
+    let mut crowded = elves.clone();
+    for (starting_direction, round) in Direction::North.zip(1..=rounds) {
+        crowded = process_round(&mut elves, &crowded, starting_direction).0;
+        if crowded.is_empty() {
+            return (elves, round);
         }
-        elves = next_elves;
     }
-    // Unreachable because we'd get a usize overflow before getting here
-    unreachable!();
+    (elves, rounds)
+}
+
+/// Return how many elves moved in each round. Stops after the first round in which no elf moved,
+/// or after the given number of rounds
+pub fn moves_per_round(mut elves: HashSet<Coord2>, max_rounds: usize) -> Vec<usize> {
+    let mut crowded = elves.clone();
+    let mut moves = Vec::new();
+    for (starting_direction, _) in Direction::North.zip(0..max_rounds) {
+        let (next_crowded, num_moved) = process_round(&mut elves, &crowded, starting_direction);
+        moves.push(num_moved);
+        if num_moved == 0 {
+            break;
+        }
+        crowded = next_crowded;
+    }
+    moves
+}
+
+/// Draw the elves within their bounding box, with north pointing up
+pub fn render(elves: &HashSet<Coord2>) -> String {
+    let Some((min, max)) = bounding_box(elves.iter().copied()) else {
+        return String::new();
+    };
+
+    (min.y..=max.y)
+        .map(|y| {
+            (min.x..=max.x)
+                .map(|x| {
+                    if elves.contains(&Coord2::new(x, y)) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Return the number of empty ground tiles within the bounding box of the elves after the given
+/// number of rounds
+pub fn empty_ground_after(elves: HashSet<Coord2>, rounds: usize) -> isize {
+    let (elves, _) = simulate(elves, rounds);
+    let Some((min, max)) = bounding_box(elves.iter().copied()) else {
+        return 0;
+    };
+    (max.x - min.x + 1) * (max.y - min.y + 1) - (elves.len() as isize)
+}
+
+fn part_a(elves: HashSet<Coord2>) -> isize {
+    empty_ground_after(elves, 10)
+}
+
+fn part_b(elves: HashSet<Coord2>) -> usize {
+    // The elves will have stopped moving long before we run out of rounds
+    let moves = moves_per_round(elves, usize::MAX);
+    moves
+        .iter()
+        .position(|&num_moved| num_moved == 0)
+        .map_or(moves.len(), |i| i + 1)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(isize, Option<usize>)> {
+    let elves = parse(input)?;
+    Ok((
+        timer.part_a(|| part_a(elves.clone())),
+        Some(timer.part_b(|| part_b(elves))),
+    ))
 }
 
 pub fn main(path: &Path) -> Result<(isize, Option<usize>)> {
-    let mut map_str = String::new();
-    File::open(path)?.read_to_string(&mut map_str)?;
-    let elves = find_elves(&map_str)?;
-    Ok((part_a(elves.clone()), Some(part_b(elves))))
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const LARGE_EXAMPLE: &'static str = concat!(
+    const LARGE_EXAMPLE: &str = concat!(
         "..............\n",
         "..............\n",
         ".......#......\n",
@@ -160,15 +227,90 @@ mod tests {
         "..............\n",
     );
 
+    const SMALL_EXAMPLE: &str =
+        concat!(".....\n", "..##.\n", "..#..\n", ".....\n", "..##.\n", ".....\n",);
+
+    #[test]
+    fn test_isolated_elf_stays_put() {
+        let isolated: HashSet<_> = [Coord2::new(0, 0), Coord2::new(2, 2)].into_iter().collect();
+        let mut elves = isolated.clone();
+        let (crowded, num_moved) = process_round(&mut elves, &isolated, Direction::North);
+        assert!(crowded.is_empty());
+        assert_eq!(num_moved, 0);
+        assert_eq!(elves, isolated);
+
+        // Diagonal neighbors count too
+        let diagonal: HashSet<_> = [Coord2::new(0, 0), Coord2::new(1, 1)].into_iter().collect();
+        let mut elves = diagonal.clone();
+        process_round(&mut elves, &diagonal, Direction::North);
+        assert_eq!(
+            elves,
+            [Coord2::new(0, -1), Coord2::new(1, 2)]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let elves = parse(SMALL_EXAMPLE)?;
+        assert_eq!(render(&elves), ["##", "#.", "..", "##"].join("\n"));
+
+        let (elves, _) = simulate(elves, 3);
+        assert_eq!(
+            render(&elves),
+            ["..#..", "....#", "#....", "....#", ".....", "..#.."].join("\n")
+        );
+        assert_eq!(render(&HashSet::new()), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_ground_after() -> Result<()> {
+        let elves = parse(SMALL_EXAMPLE)?;
+        assert_eq!(empty_ground_after(elves.clone(), 0), 3);
+        assert_eq!(empty_ground_after(elves, 3), 25);
+        assert_eq!(empty_ground_after(HashSet::new(), 10), 0);
+        Ok(())
+    }
+
     #[test]
     fn test_example_a() -> Result<()> {
-        assert_eq!(part_a(find_elves(LARGE_EXAMPLE)?), 110);
+        assert_eq!(part_a(parse(LARGE_EXAMPLE)?), 110);
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulate() -> Result<()> {
+        let elves = parse(LARGE_EXAMPLE)?;
+        let (spread, round) = simulate(elves.clone(), 10);
+        assert_eq!(round, 10);
+        assert_eq!(spread.len(), elves.len());
+        assert_ne!(spread, elves);
+
+        let (settled, round) = simulate(elves, 100);
+        assert_eq!(round, 20);
+        assert_eq!(simulate(settled.clone(), 5), (settled, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_moves_per_round() -> Result<()> {
+        let moves = moves_per_round(parse(LARGE_EXAMPLE)?, 100);
+        assert_eq!(moves.len(), 20);
+        assert_eq!(moves.last(), Some(&0));
+        assert!(moves[..19].iter().all(|&m| m > 0));
+
+        // Two elves want the same tile in the first round, so only three of the five move
+        let moves = moves_per_round(parse(SMALL_EXAMPLE)?, 100);
+        assert_eq!(moves, [3, 5, 3, 0]);
+        assert_eq!(moves_per_round(parse(SMALL_EXAMPLE)?, 2), moves[..2]);
         Ok(())
     }
 
     #[test]
     fn test_example_b() -> Result<()> {
-        assert_eq!(part_b(find_elves(LARGE_EXAMPLE)?), 20);
+        assert_eq!(part_b(parse(LARGE_EXAMPLE)?), 20);
         Ok(())
     }
 }
@@ -1,77 +1,67 @@
+use crate::coord::{Coord2, Direction};
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashSet};
-use std::fs::File;
-use std::io::Read;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-struct Coord {
-    x: isize,
-    y: isize,
-}
-
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
-
 // This represenation is kind of jank
 struct Blizzard {
-    origin: Coord,
+    origin: Coord2,
     direction: Direction,
     width: isize,
     height: isize,
 }
 
-struct Map {
-    walls: HashSet<Coord>,
+pub struct Map {
+    walls: HashSet<Coord2>,
     blizzards: Vec<Blizzard>,
-    start: Coord,
-    target: Coord,
+    width: isize,
+    height: isize,
+    /// Positions occupied by blizzards for every minute until the blizzards repeat themselves
+    occupied: Vec<HashSet<Coord2>>,
+    start: Coord2,
+    target: Coord2,
 }
 
-impl Coord {
-    fn new(x: isize, y: isize) -> Self {
-        Self { x, y }
-    }
-
-    fn iter_moves(self) -> impl Iterator<Item = Self> {
-        [
-            Self::new(self.x - 1, self.y),
-            Self::new(self.x, self.y - 1),
-            Self::new(self.x + 1, self.y),
-            Self::new(self.x, self.y + 1),
-            self,
-        ]
-        .into_iter()
-    }
-
-    fn manhattan_distance(self, other: Self) -> usize {
-        ((self.x - other.x).abs() + (self.y - other.y).abs()) as usize
+impl Direction {
+    fn symbol(&self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+            Direction::Right => '>',
+        }
     }
 }
 
 impl Blizzard {
-    fn position(&self, t: usize) -> Coord {
-        let delta = match self.direction {
-            Direction::Up => Coord::new(0, -1),
-            Direction::Down => Coord::new(0, 1),
-            Direction::Left => Coord::new(-1, 0),
-            Direction::Right => Coord::new(1, 0),
-        };
-        Coord::new(
+    fn position(&self, t: usize) -> Coord2 {
+        let delta = self.direction.delta();
+        Coord2::new(
             ((self.origin.x - 1) + delta.x * (t as isize)).rem_euclid(self.width - 2) + 1,
             ((self.origin.y - 1) + delta.y * (t as isize)).rem_euclid(self.height - 2) + 1,
         )
     }
 }
 
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
 impl Map {
+    /// Return true if a blizzard occupies the given position at the given minute
+    fn has_blizzard(&self, minute: usize, pos: Coord2) -> bool {
+        self.occupied[minute % self.occupied.len()].contains(&pos)
+    }
+
     /// Return the earliest possible time we can be at the target
-    fn earliest_arrival(&self, starting_minute: usize, start: Coord, target: Coord) -> usize {
+    fn earliest_arrival(&self, starting_minute: usize, start: Coord2, target: Coord2) -> usize {
         // Use A* to find the quickest route from start to target
         let mut to_explore = BinaryHeap::new();
         to_explore.push(Reverse((
@@ -87,12 +77,12 @@ impl Map {
             }
 
             let next_minute = curr_minute + 1;
-            for n in pos.iter_moves().filter(|c| !self.walls.contains(c)) {
-                // This could be optimized by only checking for blizzards on the same axis as the
-                // position
-                let would_hit_blizzard =
-                    self.blizzards.iter().any(|b| b.position(next_minute) == n);
-                if would_hit_blizzard {
+            for n in pos
+                .neighbors4()
+                .chain([pos])
+                .filter(|c| !self.walls.contains(c))
+            {
+                if self.has_blizzard(next_minute, n) {
                     continue;
                 }
                 if explored.insert((next_minute, n)) {
@@ -108,7 +98,40 @@ impl Map {
         unreachable!();
     }
 
-    fn try_from_str(s: &str) -> Result<Map> {
+    /// Draw the valley as it looks at the given minute. Stacked blizzards are drawn as the number
+    /// of blizzards in the same position
+    pub fn render(&self, t: usize, expedition: Option<Coord2>) -> String {
+        let mut blizzards = HashMap::new();
+        for blizzard in self.blizzards.iter() {
+            blizzards
+                .entry(blizzard.position(t))
+                .or_insert_with(Vec::new)
+                .push(blizzard);
+        }
+
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| {
+                        let c = Coord2::new(x, y);
+                        if self.walls.contains(&c) {
+                            return '#';
+                        } else if Some(c) == expedition {
+                            return 'E';
+                        }
+                        match blizzards.get(&c).map(|b| &b[..]) {
+                            None => '.',
+                            Some([blizzard]) => blizzard.direction.symbol(),
+                            Some(stacked) => char::from_digit(stacked.len() as u32, 10).unwrap(),
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn try_from_str(s: &str) -> Result<Map> {
         let mut start = None;
         let mut target = None;
         let mut width = 0;
@@ -116,9 +139,19 @@ impl Map {
         let mut walls = HashSet::new();
 
         let mut blizzard_specs = Vec::new();
+        let mut row_len = None;
         for (line, y) in s.lines().zip(0..) {
+            let len = line.chars().count();
+            if *row_len.get_or_insert(len) != len {
+                return Err(anyhow!(
+                    "Row {} has length {}, expected {}",
+                    y,
+                    len,
+                    row_len.unwrap()
+                ));
+            }
             for (c, x) in line.chars().zip(0..) {
-                let pos = Coord::new(x, y);
+                let pos = Coord2::new(x, y);
                 match c {
                     '.' if y == 0 => {
                         start = Some(pos);
@@ -144,7 +177,14 @@ impl Map {
                         height = height.max(pos.y + 1);
                         walls.insert(pos);
                     }
-                    _ => return Err(anyhow!("Unexpected character {:?} in map", c)),
+                    _ => {
+                        return Err(anyhow!(
+                            "Unexpected character {:?} in map at ({}, {})",
+                            c,
+                            x,
+                            y
+                        ))
+                    }
                 }
             }
         }
@@ -157,8 +197,8 @@ impl Map {
         };
 
         // Plug the hole behind the entrance and exit
-        walls.insert(Coord::new(start.x, start.y - 1));
-        walls.insert(Coord::new(target.x, target.y + 1));
+        walls.insert(Coord2::new(start.x, start.y - 1));
+        walls.insert(Coord2::new(target.x, target.y + 1));
 
         let blizzards = blizzard_specs
             .into_iter()
@@ -168,40 +208,73 @@ impl Map {
                 width,
                 height,
             })
+            .collect::<Vec<_>>();
+
+        // Horizontal blizzards repeat every inner width minutes and vertical ones every inner
+        // height minutes, so all of them are back where they started after the lcm of the two
+        let inner_width = (width - 2).max(1) as usize;
+        let inner_height = (height - 2).max(1) as usize;
+        let period = inner_width * inner_height / gcd(inner_width, inner_height);
+        let occupied = (0..period)
+            .map(|t| blizzards.iter().map(|b| b.position(t)).collect())
             .collect();
 
         Ok(Map {
             walls,
             blizzards,
+            width,
+            height,
+            occupied,
             start,
             target,
         })
     }
 }
 
+/// Return the earliest time we can finish crossing the valley the given number of times. Every
+/// other leg goes back from the target to the start
+fn multi_trip(map: &Map, legs: usize) -> usize {
+    let mut minute = 0;
+    let (mut from, mut to) = (map.start, map.target);
+    for _ in 0..legs {
+        minute = map.earliest_arrival(minute, from, to);
+        (from, to) = (to, from);
+    }
+    minute
+}
+
 fn part_a(map: &Map) -> usize {
-    map.earliest_arrival(0, map.start, map.target)
+    multi_trip(map, 1)
 }
 
-fn part_b(map: &Map, first_trip: usize) -> usize {
-    let back_at_start = map.earliest_arrival(first_trip, map.target, map.start);
-    map.earliest_arrival(back_at_start, map.start, map.target)
+fn part_b(map: &Map) -> usize {
+    multi_trip(map, 3)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let mut map_str = String::new();
-    File::open(path)?.read_to_string(&mut map_str)?;
-    let map = Map::try_from_str(&map_str)?;
+/// Parse the map of the valley
+pub fn parse(input: &str) -> Result<Map> {
+    Map::try_from_str(input)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let map = parse(input)?;
 
-    let first_trip = part_a(&map);
-    Ok((first_trip, Some(part_b(&map, first_trip))))
+    Ok((
+        timer.part_a(|| part_a(&map)),
+        Some(timer.part_b(|| part_b(&map))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const LARGE_EXAMPLE: &'static str = concat!(
+    const LARGE_EXAMPLE: &str = concat!(
         "#.######\n",
         "#>>.<^<#\n",
         "#.<..<<#\n",
@@ -213,44 +286,94 @@ mod tests {
     #[test]
     fn test_right_blizzard_movement() {
         let right_blizzard = Blizzard {
-            origin: Coord::new(1, 1),
+            origin: Coord2::new(1, 1),
             direction: Direction::Right,
             width: 7,
             height: 7,
         };
         assert_eq!(right_blizzard.position(0), right_blizzard.origin);
-        assert_eq!(right_blizzard.position(1), Coord::new(2, 1));
-        assert_eq!(right_blizzard.position(2), Coord::new(3, 1));
-        assert_eq!(right_blizzard.position(3), Coord::new(4, 1));
-        assert_eq!(right_blizzard.position(4), Coord::new(5, 1));
+        assert_eq!(right_blizzard.position(1), Coord2::new(2, 1));
+        assert_eq!(right_blizzard.position(2), Coord2::new(3, 1));
+        assert_eq!(right_blizzard.position(3), Coord2::new(4, 1));
+        assert_eq!(right_blizzard.position(4), Coord2::new(5, 1));
         assert_eq!(right_blizzard.position(5), right_blizzard.origin);
     }
 
     #[test]
     fn test_down_blizzard_movement() {
         let down_blizzard = Blizzard {
-            origin: Coord::new(4, 4),
+            origin: Coord2::new(4, 4),
             direction: Direction::Down,
             width: 7,
             height: 7,
         };
         assert_eq!(down_blizzard.position(0), down_blizzard.origin);
-        assert_eq!(down_blizzard.position(1), Coord::new(4, 5));
-        assert_eq!(down_blizzard.position(2), Coord::new(4, 1));
-        assert_eq!(down_blizzard.position(3), Coord::new(4, 2));
-        assert_eq!(down_blizzard.position(4), Coord::new(4, 3));
+        assert_eq!(down_blizzard.position(1), Coord2::new(4, 5));
+        assert_eq!(down_blizzard.position(2), Coord2::new(4, 1));
+        assert_eq!(down_blizzard.position(3), Coord2::new(4, 2));
+        assert_eq!(down_blizzard.position(4), Coord2::new(4, 3));
         assert_eq!(down_blizzard.position(5), down_blizzard.origin);
     }
 
     #[test]
     fn test_example_a() {
-        let map = Map::try_from_str(LARGE_EXAMPLE).unwrap();
+        let map = parse(LARGE_EXAMPLE).unwrap();
         assert_eq!(part_a(&map), 18);
     }
 
     #[test]
     fn test_example_b() {
-        let map = Map::try_from_str(LARGE_EXAMPLE).unwrap();
-        assert_eq!(part_b(&map, 18), 54);
+        let map = parse(LARGE_EXAMPLE).unwrap();
+        assert_eq!(part_b(&map), 54);
+    }
+
+    #[test]
+    fn test_ragged_map() {
+        let err = parse("#.###\n#>..#\n#...# \n###.#\n").err().unwrap();
+        assert_eq!(err.to_string(), "Row 2 has length 6, expected 5");
+        let err = parse("#.###\n#>.#\n###.#\n").err().unwrap();
+        assert_eq!(err.to_string(), "Row 1 has length 4, expected 5");
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        let err = parse("#.###\n#>.x#\n###.#\n").err().unwrap();
+        assert_eq!(err.to_string(), "Unexpected character 'x' in map at (3, 1)");
+    }
+
+    #[test]
+    fn test_render() {
+        let map = parse(LARGE_EXAMPLE).unwrap();
+        assert_eq!(map.render(0, None), LARGE_EXAMPLE.trim_end());
+        assert_eq!(
+            map.render(1, Some(Coord2::new(1, 1))),
+            concat!(
+                "#.######\n",
+                "#E>3.<.#\n",
+                "#<..<<.#\n",
+                "#>2.22.#\n",
+                "#>v..^<#\n",
+                "######.#",
+            )
+        );
+    }
+
+    #[test]
+    fn test_multi_trip() {
+        let map = parse(LARGE_EXAMPLE).unwrap();
+        assert_eq!(multi_trip(&map, 0), 0);
+        assert_eq!(multi_trip(&map, 2), 41);
+        assert_eq!(multi_trip(&map, 5), 90);
+    }
+
+    #[test]
+    fn test_blizzard_cycle() {
+        let map = parse(LARGE_EXAMPLE).unwrap();
+        assert_eq!(map.occupied.len(), 12);
+        assert!(map.has_blizzard(0, Coord2::new(1, 1)));
+        assert!(!map.has_blizzard(0, Coord2::new(3, 1)));
+        assert!(map.has_blizzard(1, Coord2::new(3, 1)));
+        assert!(map.has_blizzard(12, Coord2::new(1, 1)));
+        assert!(!map.has_blizzard(12, Coord2::new(3, 1)));
     }
 }
@@ -1,6 +1,9 @@
-use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::Read;
+use crate::Timer;
+use anyhow::{anyhow, Context, Result};
+use std::fmt;
+use std::fs;
+use std::iter::Sum;
+use std::ops::Add;
 use std::path::Path;
 use std::str::FromStr;
 
@@ -28,6 +31,28 @@ impl SnafuDigit {
         }
     }
 
+    /// Return the least significant digit of the given number when written in SNAFU
+    fn least_significant(n: isize) -> Self {
+        match n.rem_euclid(5) {
+            0 => Self::Zero,
+            1 => Self::One,
+            2 => Self::Two,
+            3 => Self::DoubleMinus,
+            4 => Self::Minus,
+            _ => unreachable!(),
+        }
+    }
+
+    fn value(self) -> isize {
+        match self {
+            Self::DoubleMinus => -2,
+            Self::Minus => -1,
+            Self::Zero => 0,
+            Self::One => 1,
+            Self::Two => 2,
+        }
+    }
+
     fn to_char(self) -> char {
         match self {
             Self::DoubleMinus => '=',
@@ -41,31 +66,13 @@ impl SnafuDigit {
 
 impl SnafuNumber {
     fn new(mut n: isize) -> Self {
-        if n < 0 {
-            panic!("Negative numbers are currently not handled");
-        }
-
+        // The digits are symmetric around zero, so the same conversion works for negative numbers
+        // as long as we use the euclidean remainder
         let mut snafu_digits = Vec::new();
-        let mut carry = 0;
-        while n > 0 {
-            let rem = n % 5;
-
-            if rem == 4 {
-                carry += 1;
-                snafu_digits.push(SnafuDigit::Minus);
-            } else if rem == 3 {
-                carry += 1;
-                snafu_digits.push(SnafuDigit::DoubleMinus);
-            } else if rem == 2 {
-                snafu_digits.push(SnafuDigit::Two);
-            } else if rem == 1 {
-                snafu_digits.push(SnafuDigit::One);
-            } else {
-                snafu_digits.push(SnafuDigit::Zero);
-            }
-
-            n = (n - rem) / 5 + carry;
-            carry = 0;
+        while n != 0 {
+            let digit = SnafuDigit::least_significant(n);
+            snafu_digits.push(digit);
+            n = (n - digit.value()) / 5;
         }
         if snafu_digits.is_empty() {
             snafu_digits.push(SnafuDigit::Zero);
@@ -78,65 +85,108 @@ impl SnafuNumber {
             .iter()
             .copied()
             .zip(0..)
-            .map(|(s, i)| {
-                let multiplier = match s {
-                    SnafuDigit::DoubleMinus => -2,
-                    SnafuDigit::Minus => -1,
-                    SnafuDigit::Zero => 0,
-                    SnafuDigit::One => 1,
-                    SnafuDigit::Two => 2,
-                };
-                multiplier * 5isize.pow(i)
-            })
+            .map(|(s, i)| s.value() * 5isize.pow(i))
             .sum()
     }
 }
 
+impl Add for SnafuNumber {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        // Add column by column. The sum of a column including the carry is always within -5..=5,
+        // so the carry is always one of -1, 0 or 1
+        let digit_value = |n: &Self, i: usize| n.0.get(i).map_or(0, |d| d.value());
+        let mut snafu_digits = Vec::new();
+        let mut carry = 0;
+        for i in 0..self.0.len().max(rhs.0.len()) {
+            let column_sum = digit_value(&self, i) + digit_value(&rhs, i) + carry;
+            let digit = SnafuDigit::least_significant(column_sum);
+            snafu_digits.push(digit);
+            carry = (column_sum - digit.value()) / 5;
+        }
+        if carry != 0 {
+            snafu_digits.push(SnafuDigit::least_significant(carry));
+        }
+
+        // Remove leading zeros from digits that cancelled each other out
+        while snafu_digits.len() > 1 && snafu_digits.last() == Some(&SnafuDigit::Zero) {
+            snafu_digits.pop();
+        }
+        Self(snafu_digits)
+    }
+}
+
+impl Sum for SnafuNumber {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::new(0), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a SnafuNumber> for SnafuNumber {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.cloned().sum()
+    }
+}
+
 impl FromStr for SnafuNumber {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(
-            s.chars()
-                .rev()
-                .map(SnafuDigit::from_char)
-                .collect::<Result<Vec<_>>>()?,
-        ))
+        if s.is_empty() {
+            return Err(anyhow!("SNAFU number must have at least one digit"));
+        }
+        let snafu_digits = s
+            .chars()
+            .rev()
+            .map(SnafuDigit::from_char)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid SNAFU number {:?}", s))?;
+        Ok(Self(snafu_digits))
     }
 }
 
-impl ToString for SnafuNumber {
-    fn to_string(&self) -> String {
-        self.0
-            .iter()
-            .copied()
-            .rev()
-            .map(SnafuDigit::to_char)
-            .collect()
+impl fmt::Display for SnafuNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for digit in self.0.iter().rev() {
+            write!(f, "{}", digit.to_char())?;
+        }
+        Ok(())
     }
 }
 
 fn part_a(snafu_numbers: &[SnafuNumber]) -> String {
-    let sum = snafu_numbers.iter().map(SnafuNumber::to_isize).sum();
-    SnafuNumber::new(sum).to_string()
+    snafu_numbers.iter().sum::<SnafuNumber>().to_string()
 }
 
-pub fn main(path: &Path) -> Result<(String, Option<usize>)> {
-    let mut snafu_numbers_str = String::new();
-    File::open(path)?.read_to_string(&mut snafu_numbers_str)?;
-    let snafu_numbers = snafu_numbers_str
+/// There is no second puzzle on the last day, so we return the decimal sum of the fuel
+/// requirements instead
+fn part_b(snafu_numbers: &[SnafuNumber]) -> isize {
+    snafu_numbers.iter().map(SnafuNumber::to_isize).sum()
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(String, Option<isize>)> {
+    let snafu_numbers = input
         .lines()
         .map(SnafuNumber::from_str)
         .collect::<Result<Vec<_>>>()?;
 
-    Ok((part_a(&snafu_numbers), None))
+    Ok((
+        timer.part_a(|| part_a(&snafu_numbers)),
+        Some(timer.part_b(|| part_b(&snafu_numbers))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(String, Option<isize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE_PAIRS: &'static [(isize, &'static str)] = &[
+    const EXAMPLE_PAIRS: &[(isize, &str)] = &[
         (0, "0"),
         (1, "1"),
         (2, "2"),
@@ -175,4 +225,87 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_negative_numbers() -> Result<()> {
+        assert_eq!(SnafuNumber::new(-1).to_string(), "-");
+        assert_eq!(SnafuNumber::new(-3).to_string(), "-2");
+        assert_eq!(SnafuNumber::new(-2022).to_string(), "-2--1=");
+        for n in -2022..=2022 {
+            let snafu_number = SnafuNumber::new(n);
+            assert_eq!(snafu_number.to_isize(), n);
+            assert_eq!(
+                SnafuNumber::from_str(&snafu_number.to_string())?.to_isize(),
+                n
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_add() -> Result<()> {
+        let two = SnafuNumber::new(2);
+        assert_eq!((two.clone() + two.clone() + two).to_string(), "11");
+        assert_eq!(
+            (SnafuNumber::from_str("2222")? + SnafuNumber::from_str("2222")?).to_string(),
+            "1000-"
+        );
+        assert_eq!(
+            (SnafuNumber::from_str("1=")? + SnafuNumber::new(-3)).to_string(),
+            "0"
+        );
+        assert_eq!(
+            (SnafuNumber::new(-1000) + SnafuNumber::new(1)).to_isize(),
+            -999
+        );
+        for a in -30..=30 {
+            for b in -30..=30 {
+                assert_eq!(
+                    (SnafuNumber::new(a) + SnafuNumber::new(b)).to_isize(),
+                    a + b
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(format!("{}", SnafuNumber::new(2022)), "1=11-2");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(SnafuNumber::from_str("").is_err());
+        assert!(SnafuNumber::from_str("1=3").is_err());
+        assert!(SnafuNumber::from_str("1 2").is_err());
+        assert!(SnafuNumber::from_str("12\n").is_err());
+    }
+
+    fn example_numbers() -> Vec<SnafuNumber> {
+        [
+            "1=-0-2", "12111", "2=0=", "21", "2=01", "111", "20012", "112", "1=-1=", "1-12", "12",
+            "1=", "122",
+        ]
+        .into_iter()
+        .map(|s| s.parse().unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_example_a() {
+        assert_eq!(part_a(&example_numbers()), "2=-1=0");
+    }
+
+    #[test]
+    fn test_sum_matches_decimal_sum() {
+        let sum = example_numbers().into_iter().sum::<SnafuNumber>();
+        assert_eq!(sum.to_isize(), part_b(&example_numbers()));
+        assert_eq!(sum.to_string(), SnafuNumber::new(4890).to_string());
+    }
+
+    #[test]
+    fn test_example_b() {
+        assert_eq!(part_b(&example_numbers()), 4890);
+    }
 }
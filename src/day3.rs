@@ -1,7 +1,7 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
 
 fn parse_line(line: &str) -> Result<Vec<usize>> {
@@ -14,15 +14,26 @@ fn parse_line(line: &str) -> Result<Vec<usize>> {
         .collect()
 }
 
+/// Return the items that are in every one of the rucksacks, or nothing if there are no rucksacks
+fn common_items(rucksacks: &[&[usize]]) -> HashSet<usize> {
+    let Some((first, rest)) = rucksacks.split_first() else {
+        return HashSet::new();
+    };
+    let mut common = first.iter().copied().collect::<HashSet<_>>();
+    for r in rest {
+        common.retain(|item| r.contains(item));
+    }
+    common
+}
+
 fn part_a(rucksacks: &[Vec<usize>]) -> Result<usize> {
     let mut sum = 0;
     for r in rucksacks {
         if r.len() % 2 == 1 {
             return Err(anyhow!("Rucksack does not have an even number of elements"));
         }
-        let a: HashSet<usize> = r.iter().copied().take(r.len() / 2).collect::<HashSet<_>>();
-        let b: HashSet<usize> = r.iter().copied().skip(r.len() / 2).collect::<HashSet<_>>();
-        sum += a.intersection(&b).sum::<usize>();
+        let (a, b) = r.split_at(r.len() / 2);
+        sum += common_items(&[a, b]).into_iter().sum::<usize>();
     }
     Ok(sum)
 }
@@ -30,31 +41,34 @@ fn part_a(rucksacks: &[Vec<usize>]) -> Result<usize> {
 fn part_b(rucksacks: &[Vec<usize>]) -> Result<usize> {
     let mut sum = 0;
     for triplets in rucksacks.chunks(3) {
-        let a: HashSet<usize> = triplets[0].iter().copied().collect();
-        let b: HashSet<usize> = triplets[1].iter().copied().collect();
-        let c: HashSet<usize> = triplets[2].iter().copied().collect();
-        sum += a
-            .intersection(&b.intersection(&c).copied().collect())
-            .sum::<usize>();
+        if triplets.len() != 3 {
+            return Err(anyhow!("Last group only has {} rucksacks", triplets.len()));
+        }
+        let group = triplets.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        sum += common_items(&group).into_iter().sum::<usize>();
     }
     Ok(sum)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let rucksacks = io::BufReader::new(file)
-        .lines()
-        .map(|lr| parse_line(&lr?))
-        .collect::<Result<Vec<_>>>()?;
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let rucksacks = input.lines().map(parse_line).collect::<Result<Vec<_>>>()?;
 
-    Ok((part_a(&rucksacks)?, Some(part_b(&rucksacks)?)))
+    Ok((
+        timer.part_a(|| part_a(&rucksacks))?,
+        Some(timer.part_b(|| part_b(&rucksacks))?),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static [&'static str] = &[
+    const INPUT: &[&str] = &[
         "vJrwpWtwJgWrhcsFMMfFFhFp",
         "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
         "PmmdzqPrVvPwwTWBwg",
@@ -63,6 +77,26 @@ mod tests {
         "CrZsJsPPZsGzwwsLwLmpwMDw",
     ];
 
+    #[test]
+    fn test_common_items() {
+        let set = |items: &[usize]| items.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(common_items(&[&[1, 2, 2, 3]]), set(&[1, 2, 3]));
+        assert_eq!(common_items(&[&[1, 2, 3], &[3, 4, 1]]), set(&[1, 3]));
+        assert_eq!(common_items(&[&[1, 2, 3], &[3, 4, 1], &[5, 3]]), set(&[3]));
+        assert_eq!(common_items(&[&[1, 2], &[3, 4]]), set(&[]));
+        assert_eq!(common_items(&[]), set(&[]));
+    }
+
+    #[test]
+    fn test_incomplete_group() -> Result<()> {
+        let rucksacks = INPUT[..4]
+            .iter()
+            .map(|l| parse_line(l))
+            .collect::<Result<Vec<_>>>()?;
+        assert!(part_b(&rucksacks).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_example_a() -> Result<()> {
         let rucksacks = INPUT
@@ -1,6 +1,7 @@
+use crate::input::data_lines;
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::ops::RangeInclusive;
 use std::path::Path;
 
@@ -13,50 +14,65 @@ fn parse_range(s: &str) -> Result<RangeInclusive<usize>> {
     Ok(start.parse()?..=end.parse()?)
 }
 
-fn part_a(pairs: &[Pair]) -> usize {
+/// Return true if one of the ranges contains the other
+fn is_fully_contained((a, b): &Pair) -> bool {
+    a.contains(b.start()) && a.contains(b.end()) || b.contains(a.start()) && b.contains(a.end())
+}
+
+/// Return true if the ranges have at least one section in common
+fn is_overlapping((a, b): &Pair) -> bool {
+    a.contains(b.start()) || a.contains(b.end()) || b.contains(a.start()) || b.contains(a.end())
+}
+
+/// Count the pairs where one range fully contains the other and the pairs that overlap at all,
+/// in a single pass
+pub fn count_overlaps(pairs: &[Pair]) -> (usize, usize) {
     pairs
         .iter()
-        .filter(|(a, b)| {
-            a.contains(b.start()) && a.contains(b.end())
-                || b.contains(a.start()) && b.contains(a.end())
+        .fold((0, 0), |(fully_contained, any_overlap), pair| {
+            (
+                fully_contained + usize::from(is_fully_contained(pair)),
+                any_overlap + usize::from(is_overlapping(pair)),
+            )
         })
-        .count()
+}
+
+fn part_a(pairs: &[Pair]) -> usize {
+    pairs.iter().filter(|p| is_fully_contained(p)).count()
 }
 
 fn part_b(pairs: &[Pair]) -> usize {
-    pairs
-        .iter()
-        .filter(|(a, b)| {
-            a.contains(b.start())
-                || a.contains(b.end())
-                || b.contains(a.start())
-                || b.contains(a.end())
-        })
-        .count()
+    pairs.iter().filter(|p| is_overlapping(p)).count()
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let pairs = io::BufReader::new(file)
-        .lines()
-        .map(|lr| {
-            let pair = lr?;
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let pairs = data_lines(input.as_bytes())
+        .map(|pair| {
+            let pair = pair?;
             let Some((a, b)) = pair.split_once(',') else {
-            return Err(anyhow!("Pair doesn't contain a comma"));
-        };
+                return Err(anyhow!("Pair doesn't contain a comma"));
+            };
 
             Ok((parse_range(a)?, parse_range(b)?))
         })
         .collect::<Result<Vec<_>>>()?;
 
-    Ok((part_a(&pairs), Some(part_b(&pairs))))
+    Ok((
+        timer.part_a(|| part_a(&pairs)),
+        Some(timer.part_b(|| part_b(&pairs))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const INPUT: &'static [Pair] = &[
+    const INPUT: &[Pair] = &[
         (2..=4, 6..=8),
         (2..=3, 4..=5),
         (5..=7, 7..=9),
@@ -65,6 +81,20 @@ mod tests {
         (2..=6, 4..=8),
     ];
 
+    #[test]
+    fn test_comments_and_blank_lines() -> Result<()> {
+        let annotated = "# Disjoint\n2-4,6-8\n\n# Contained\n2-8,3-7\n";
+        assert_eq!(solve(annotated, &mut Timer::new())?, (1, Some(1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_overlaps() {
+        assert_eq!(count_overlaps(INPUT), (2, 4));
+        assert_eq!(count_overlaps(INPUT), (part_a(INPUT), part_b(INPUT)));
+        assert_eq!(count_overlaps(&[]), (0, 0));
+    }
+
     #[test]
     fn test_example_a() {
         assert_eq!(part_a(INPUT), 2);
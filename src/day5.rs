@@ -1,8 +1,8 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::fs::File;
-use std::io::Read;
+use std::fs;
 use std::iter::repeat_with;
 use std::path::Path;
 use std::str::FromStr;
@@ -55,6 +55,11 @@ fn parse_stacks(s: &str) -> Result<Vec<Vec<char>>> {
     Ok(stacks)
 }
 
+/// Return the crate on top of each stack, or None for stacks that are empty
+pub fn top_crates(stacks: &[Vec<char>]) -> Vec<Option<char>> {
+    stacks.iter().map(|s| s.last().copied()).collect()
+}
+
 fn part_a(mut stacks: Vec<Vec<char>>, procedures: &[Procedure]) -> Result<String> {
     for p in procedures {
         for _ in 0..p.num_crates {
@@ -64,10 +69,7 @@ fn part_a(mut stacks: Vec<Vec<char>>, procedures: &[Procedure]) -> Result<String
             stacks[p.to].push(c);
         }
     }
-    Ok(stacks
-        .into_iter()
-        .filter_map(|e| e.last().copied())
-        .collect())
+    Ok(top_crates(&stacks).into_iter().flatten().collect())
 }
 
 fn part_b(mut stacks: Vec<Vec<char>>, procedures: &[Procedure]) -> Result<String> {
@@ -76,18 +78,15 @@ fn part_b(mut stacks: Vec<Vec<char>>, procedures: &[Procedure]) -> Result<String
         let moved_crates = stacks[p.from].split_off(num_crates_kept);
         stacks[p.to].extend(moved_crates);
     }
-    Ok(stacks
-        .into_iter()
-        .filter_map(|e| e.last().copied())
-        .collect())
+    Ok(top_crates(&stacks).into_iter().flatten().collect())
 }
 
-pub fn main(path: &Path) -> Result<(String, Option<String>)> {
-    let mut input = String::new();
-    File::open(path)?.read_to_string(&mut input)?;
-
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(String, Option<String>)> {
     let Some((stacks_str, procedures_str)) = input.split_once("\n\n") else {
-        return Err(anyhow!("Unable to split input into crate configuration and move procedures"));
+        return Err(anyhow!(
+            "Unable to split input into crate configuration and move procedures"
+        ));
     };
 
     let stacks = parse_stacks(stacks_str)?;
@@ -97,11 +96,15 @@ pub fn main(path: &Path) -> Result<(String, Option<String>)> {
         .collect::<Result<Vec<Procedure>>>()?;
 
     Ok((
-        part_a(stacks.clone(), &procedures)?,
-        Some(part_b(stacks, &procedures)?),
+        timer.part_a(|| part_a(stacks.clone(), &procedures))?,
+        Some(timer.part_b(|| part_b(stacks, &procedures))?),
     ))
 }
 
+pub fn main(path: &Path) -> Result<(String, Option<String>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,6 +127,22 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_emptied_stack() -> Result<()> {
+        let procedures = ["move 3 from 2 to 1".parse()?];
+        assert_eq!(
+            top_crates(&EXAMPLE_STACKS),
+            [Some('N'), Some('D'), Some('P')]
+        );
+
+        // The middle stack is emptied onto the first one
+        let stacks = vec![vec!['Z', 'N', 'D', 'C', 'M'], vec![], vec!['P']];
+        assert_eq!(top_crates(&stacks), [Some('M'), None, Some('P')]);
+        assert_eq!(part_a(EXAMPLE_STACKS.clone(), &procedures)?, "MP");
+        assert_eq!(part_b(EXAMPLE_STACKS.clone(), &procedures)?, "DP");
+        Ok(())
+    }
+
     #[test]
     fn test_example_b() -> Result<()> {
         assert_eq!(part_b(EXAMPLE_STACKS.clone(), &EXAMPLE_PROCEDURES)?, "MCD");
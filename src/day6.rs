@@ -1,29 +1,102 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufReader, Read};
 use std::path::Path;
 
+/// Return the first byte of the window that occurs more than once in it, or None if every byte
+/// is distinct and the window is a marker
+fn blocking_char(window: &[u8]) -> Option<u8> {
+    let mut counts = [0usize; 256];
+    for &byte in window {
+        counts[byte as usize] += 1;
+    }
+    window
+        .iter()
+        .copied()
+        .find(|&byte| counts[byte as usize] > 1)
+}
+
+/// Return the number of bytes up to and including the first marker. An empty marker is found
+/// before reading anything
 fn find_packet_start(input: &[u8], marker_size: usize) -> Option<usize> {
+    if marker_size == 0 {
+        return Some(0);
+    }
     for (i, window) in input.windows(marker_size).enumerate() {
-        if window.iter().copied().collect::<HashSet<_>>().len() == marker_size {
+        if blocking_char(window).is_none() {
             return Some(i + marker_size);
         }
     }
     None
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let buf = File::open(path)?.bytes().collect::<Result<Vec<u8>, _>>()?;
+/// Find the end of the first marker while reading the datastream incrementally. Reading stops as
+/// soon as a marker is found, so the datastream never has to fit in memory. Behaves like
+/// find_packet_start, including for empty markers
+fn find_packet_start_stream(reader: impl Read, marker_size: usize) -> Result<Option<usize>> {
+    if marker_size == 0 {
+        return Ok(Some(0));
+    }
+
+    // Keep track of how many times each byte occurs in the window and how many distinct bytes
+    // there are, so every step only has to look at the bytes entering and leaving the window
+    let mut counts = [0usize; 256];
+    let mut num_distinct = 0;
+    let mut window = VecDeque::with_capacity(marker_size + 1);
+
+    for (i, byte) in BufReader::new(reader).bytes().enumerate() {
+        let byte = byte?;
+        window.push_back(byte);
+        counts[byte as usize] += 1;
+        if counts[byte as usize] == 1 {
+            num_distinct += 1;
+        }
+        if window.len() > marker_size {
+            let old = window.pop_front().unwrap();
+            counts[old as usize] -= 1;
+            if counts[old as usize] == 0 {
+                num_distinct -= 1;
+            }
+        }
+        if num_distinct == marker_size {
+            return Ok(Some(i + 1));
+        }
+    }
+    Ok(None)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let buf = input.as_bytes();
     Ok((
-        find_packet_start(&buf, 4).ok_or_else(|| anyhow!("Couldn't find start of packet"))?,
-        Some(find_packet_start(&buf, 14).ok_or_else(|| anyhow!("Couldn't find start of packet"))?),
+        timer
+            .part_a(|| find_packet_start(buf, 4))
+            .ok_or_else(|| anyhow!("Couldn't find start of packet"))?,
+        Some(
+            timer
+                .part_b(|| find_packet_start(buf, 14))
+                .ok_or_else(|| anyhow!("Couldn't find start of packet"))?,
+        ),
     ))
 }
 
+/// Solve both parts by streaming the file once per part instead of reading it into memory. Only
+/// this entry point streams, since run and the benchmarks already have the whole input in memory
+/// and go through solve
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    let find = |marker_size| {
+        find_packet_start_stream(File::open(path)?, marker_size)?
+            .ok_or_else(|| anyhow!("Couldn't find start of packet"))
+    };
+    Ok((find(4)?, Some(find(14)?)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
 
     #[test]
     fn test_example_a() -> Result<()> {
@@ -74,4 +147,67 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_blocking_char() {
+        assert_eq!(blocking_char(b"mjqj"), Some(b'j'));
+        assert_eq!(blocking_char(b"jpqm"), None);
+        assert_eq!(blocking_char(b"bvwb"), Some(b'b'));
+        assert_eq!(blocking_char(b"nppd"), Some(b'p'));
+        assert_eq!(blocking_char(b"nznr"), Some(b'n'));
+        assert_eq!(blocking_char(b"zcfz"), Some(b'z'));
+        assert_eq!(blocking_char(b"abba"), Some(b'a'));
+        assert_eq!(blocking_char(b""), None);
+
+        // Every window before the marker is blocked by some character
+        let example = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let blocked = example
+            .windows(14)
+            .map_while(blocking_char)
+            .collect::<Vec<_>>();
+        assert_eq!(blocked.len(), 19 - 14);
+        assert_eq!(blocked[0], b'm');
+    }
+
+    #[test]
+    fn test_stream_matches_slice() -> Result<()> {
+        for example in [
+            &b"mjqjpqmgbljsphdztnvjfqwrcgsmlb"[..],
+            b"bvwbjplbgvbhsrlpgdmjqwftvncz",
+            b"nppdvjthqldpwncqszvftbrmjlhg",
+            b"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg",
+            b"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw",
+            b"aaaaaaaaaaaaaaaaaaaa",
+        ] {
+            for marker_size in [0, 1, 4, 14] {
+                assert_eq!(
+                    find_packet_start_stream(example, marker_size)?,
+                    find_packet_start(example, marker_size)
+                );
+            }
+        }
+        assert_eq!(find_packet_start_stream(&b""[..], 4)?, None);
+        assert_eq!(find_packet_start_stream(&b""[..], 0)?, Some(0));
+        assert_eq!(find_packet_start(b"", 0), Some(0));
+        Ok(())
+    }
+
+    /// Reader that fails if anyone tries to read from it
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("Read past the marker"))
+        }
+    }
+
+    #[test]
+    fn test_stream_stops_at_marker() -> Result<()> {
+        assert_eq!(
+            find_packet_start_stream(b"aabcd".chain(FailingReader), 4)?,
+            Some(5)
+        );
+        assert!(find_packet_start_stream(b"aabc".chain(FailingReader), 4).is_err());
+        Ok(())
+    }
 }
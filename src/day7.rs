@@ -1,11 +1,13 @@
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::convert::Infallible;
+use std::fs;
+use std::iter;
 use std::path::Path;
 
 #[derive(Debug, Clone, Default)]
-struct DirectoryListing {
+pub struct DirectoryListing {
     dirs: HashMap<String, DirectoryListing>,
     files: HashMap<String, usize>,
 }
@@ -19,6 +21,59 @@ impl DirectoryListing {
         self.direct_size() + self.dirs.values().map(|d| d.total_size()).sum::<usize>()
     }
 
+    /// Return the directory at the given path relative to this one
+    pub fn get(&self, path: &[&str]) -> Option<&Self> {
+        let mut listing = Some(self);
+        for dir_name in path {
+            listing = listing?.dirs.get(*dir_name);
+        }
+        listing
+    }
+
+    /// Return the total size of the directory at the given path relative to this one
+    pub fn total_size_at(&self, path: &[&str]) -> Option<usize> {
+        self.get(path).map(Self::total_size)
+    }
+
+    /// Iterate over this directory and all its subdirectories along with their paths relative to
+    /// this one. This directory is yielded first with an empty path
+    pub fn iter_dirs(&self) -> impl Iterator<Item = (Vec<String>, &Self)> {
+        let mut stack = vec![(Vec::new(), self)];
+        iter::from_fn(move || {
+            let (path, listing) = stack.pop()?;
+            for (name, dir) in listing.dirs.iter() {
+                let mut dir_path = path.clone();
+                dir_path.push(name.clone());
+                stack.push((dir_path, dir));
+            }
+            Some((path, listing))
+        })
+    }
+
+    /// Return the path and total size of the smallest directory that would free up at least
+    /// `needed` bytes if removed. Ties are broken by picking the lexicographically smallest path
+    pub fn smallest_dir_to_free(&self, needed: usize) -> Option<(Vec<String>, usize)> {
+        self.iter_dirs()
+            .map(|(path, d)| (path, d.total_size()))
+            .filter(|(_, size)| *size >= needed)
+            .min_by(|(a_path, a_size), (b_path, b_size)| {
+                a_size.cmp(b_size).then_with(|| a_path.cmp(b_path))
+            })
+    }
+
+    /// Return the path and total size of this directory and all its subdirectories, largest first.
+    /// Directories of the same size are ordered by path
+    pub fn sizes_descending(&self) -> Vec<(Vec<String>, usize)> {
+        let mut sizes = self
+            .iter_dirs()
+            .map(|(path, d)| (path, d.total_size()))
+            .collect::<Vec<_>>();
+        sizes.sort_by(|(a_path, a_size), (b_path, b_size)| {
+            b_size.cmp(a_size).then_with(|| a_path.cmp(b_path))
+        });
+        sizes
+    }
+
     fn cd(&mut self, path: &[String]) -> Option<&mut Self> {
         let mut listing = Some(self);
         for dir_name in path {
@@ -27,10 +82,17 @@ impl DirectoryListing {
         listing
     }
 
+    /// Like `cd`, but creates any missing directories along the way
+    fn cd_create(&mut self, path: &[String]) -> &mut Self {
+        let mut listing = self;
+        for dir_name in path {
+            listing = listing.dirs.entry(dir_name.clone()).or_default();
+        }
+        listing
+    }
+
     fn add_dir(&mut self, name: &str) {
-        self.dirs
-            .entry(name.to_owned())
-            .or_insert_with(Default::default);
+        self.dirs.entry(name.to_owned()).or_default();
     }
 
     fn add_file(&mut self, name: &str, size: usize) {
@@ -39,8 +101,10 @@ impl DirectoryListing {
 }
 
 fn part_a(dl: &DirectoryListing) -> usize {
-    let total_size = dl.total_size();
-    dl.dirs.values().map(part_a).sum::<usize>() + if total_size <= 100_000 { total_size } else { 0 }
+    dl.iter_dirs()
+        .map(|(_, d)| d.total_size())
+        .filter(|&s| s <= 100_000)
+        .sum()
 }
 
 fn part_b(dl: &DirectoryListing) -> usize {
@@ -49,52 +113,52 @@ fn part_b(dl: &DirectoryListing) -> usize {
     let required_free_space = 30_000_000;
     let needs_freeing = used + required_free_space - capacity;
 
-    let mut stack = vec![dl];
-    let mut total_sizes = Vec::new();
-    while let Some(d) = stack.pop() {
-        stack.extend(d.dirs.values());
-        total_sizes.push(d.total_size());
-    }
-
-    total_sizes.sort();
-
     // It's OK to unwrap since capacity is greater than free space and we can always remove all the
     // files
-    total_sizes
-        .into_iter()
-        .find(|s| s >= &needs_freeing)
+    dl.smallest_dir_to_free(needs_freeing)
+        .map(|(_, size)| size)
         .unwrap()
 }
 
-fn parse_terminal_output<E>(
-    lines: impl Iterator<Item = Result<String, E>>,
-) -> Result<DirectoryListing>
-where
-    E: std::error::Error + Sync + Send + 'static,
-{
-    let mut root = DirectoryListing::default();
-    let mut cwd: Vec<String> = Vec::new();
-    let mut read_stdout = false;
-    for lr in lines {
-        let line = lr?;
-        match line.as_str() {
+/// State of the terminal session while it's being parsed
+#[derive(Default)]
+struct TerminalParser {
+    root: DirectoryListing,
+    cwd: Vec<String>,
+    read_stdout: bool,
+}
+
+impl TerminalParser {
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        match line {
             "$ cd /" => {
-                cwd = Vec::new();
-                read_stdout = false;
+                self.cwd = Vec::new();
+                self.read_stdout = false;
             }
             "$ cd .." => {
-                cwd.pop();
-                read_stdout = false;
+                self.cwd.pop();
+                self.read_stdout = false;
             }
             "$ ls" => {
-                read_stdout = true;
+                self.read_stdout = true;
+            }
+            _ if line.starts_with("$ cd /") => {
+                self.cwd = line[6..]
+                    .split('/')
+                    .filter(|dir_name| !dir_name.is_empty())
+                    .map(ToString::to_string)
+                    .collect();
+                self.root.cd_create(&self.cwd);
+                self.read_stdout = false;
             }
             _ if line.starts_with("$ cd ") => {
-                cwd.push(line[5..].to_string());
-                read_stdout = false;
+                self.cwd.push(line[5..].to_string());
+                self.read_stdout = false;
             }
-            _ if read_stdout => {
-                let curr_dir = root.cd(&cwd).unwrap();
+            _ if self.read_stdout => {
+                let Some(curr_dir) = self.root.cd(&self.cwd) else {
+                    return Err(anyhow!("cd into unknown directory /{}", self.cwd.join("/")));
+                };
                 if let Some(dir_name) = line.strip_prefix("dir ") {
                     curr_dir.add_dir(dir_name);
                 } else if let Some((size_str, name)) = line.split_once(' ') {
@@ -105,20 +169,49 @@ where
             }
             _ => return Err(anyhow!("Unknown input line {:?}", line)),
         }
+        Ok(())
     }
-    Ok(root)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let root = parse_terminal_output(io::BufReader::new(file).lines())?;
+pub fn parse_terminal_output<E>(
+    lines: impl Iterator<Item = Result<String, E>>,
+) -> Result<DirectoryListing>
+where
+    E: std::error::Error + Sync + Send + 'static,
+{
+    let mut parser = TerminalParser::default();
+    for (i, lr) in lines.enumerate() {
+        // Line numbers start at 1 like in an editor
+        let line_no = i + 1;
+        let line = lr.map_err(|e| anyhow!("line {}: {}", line_no, e))?;
+        parser
+            .parse_line(&line)
+            .map_err(|e| anyhow!("line {}: {}", line_no, e))?;
+    }
+    Ok(parser.root)
+}
 
-    Ok((part_a(&root), Some(part_b(&root))))
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let lines = input
+        .lines()
+        .map(|line| Ok::<_, Infallible>(line.to_owned()));
+    let root = parse_terminal_output(lines)?;
+
+    Ok((
+        timer.part_a(|| part_a(&root)),
+        Some(timer.part_b(|| part_b(&root))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
 
     fn root() -> Result<DirectoryListing> {
         let lines = [
@@ -161,4 +254,105 @@ mod tests {
         assert_eq!(part_b(&root()?), 24_933_642);
         Ok(())
     }
+
+    #[test]
+    fn test_total_size_at() -> Result<()> {
+        let root = root()?;
+        assert_eq!(root.total_size_at(&[]), Some(48_381_165));
+        assert_eq!(root.total_size_at(&["a", "e"]), Some(584));
+        assert_eq!(root.total_size_at(&["a", "x"]), None);
+        assert!(root.get(&["d"]).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_iter_dirs() -> Result<()> {
+        let root = root()?;
+        let mut paths = root.iter_dirs().map(|(p, _)| p).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                vec![],
+                vec!["a".to_owned()],
+                vec!["a".to_owned(), "e".to_owned()],
+                vec!["d".to_owned()],
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_smallest_dir_to_free() -> Result<()> {
+        let root = root()?;
+        assert_eq!(
+            root.smallest_dir_to_free(8_381_165),
+            Some((vec!["d".to_owned()], 24_933_642))
+        );
+        assert_eq!(root.smallest_dir_to_free(100_000_000), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sizes_descending() -> Result<()> {
+        assert_eq!(
+            root()?.sizes_descending(),
+            vec![
+                (vec![], 48_381_165),
+                (vec!["d".to_owned()], 24_933_642),
+                (vec!["a".to_owned()], 94_853),
+                (vec!["a".to_owned(), "e".to_owned()], 584),
+            ]
+        );
+
+        // Directories of the same size are ordered by path
+        let lines = ["$ cd /", "$ ls", "dir b", "dir a"]
+            .into_iter()
+            .map(|l| Ok::<_, io::Error>(l.to_owned()));
+        let sizes = parse_terminal_output(lines)?.sizes_descending();
+        let paths = sizes
+            .into_iter()
+            .map(|(p, _)| p.join("/"))
+            .collect::<Vec<_>>();
+        assert_eq!(paths, ["", "a", "b"]);
+        Ok(())
+    }
+
+    fn parse_error(lines: &[&str]) -> String {
+        let lines = lines.iter().map(|l| Ok::<_, io::Error>(l.to_string()));
+        parse_terminal_output(lines).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_error_line_numbers() {
+        assert_eq!(
+            parse_error(&["dir a", "$ ls"]),
+            "line 1: Unknown input line \"dir a\""
+        );
+        assert_eq!(
+            parse_error(&["$ cd /", "$ ls", "dir a", "$ cd a", "$ cd b", "$ ls", "1 c"]),
+            "line 7: cd into unknown directory /a/b"
+        );
+        assert!(parse_error(&["$ ls", "dir a", "x b"]).starts_with("line 3: "));
+    }
+
+    #[test]
+    fn test_cd_absolute_path() -> Result<()> {
+        let lines = [
+            Ok::<_, io::Error>("$ cd /a/e".to_owned()),
+            Ok::<_, io::Error>("$ ls".to_owned()),
+            Ok::<_, io::Error>("584 i".to_owned()),
+            Ok::<_, io::Error>("$ cd ..".to_owned()),
+            Ok::<_, io::Error>("$ ls".to_owned()),
+            Ok::<_, io::Error>("29116 f".to_owned()),
+            Ok::<_, io::Error>("$ cd /".to_owned()),
+            Ok::<_, io::Error>("$ cd d".to_owned()),
+        ]
+        .into_iter();
+        let root = parse_terminal_output(lines)?;
+        assert_eq!(root.total_size_at(&["a", "e"]), Some(584));
+        assert_eq!(root.total_size_at(&["a"]), Some(29_700));
+        assert_eq!(root.total_size_at(&[]), Some(29_700));
+        Ok(())
+    }
 }
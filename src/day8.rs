@@ -1,112 +1,122 @@
+use crate::coord::Coord2;
+use crate::grid::{Cells, DenseGrid};
+use crate::Timer;
 use anyhow::{anyhow, Result};
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::cmp::Reverse;
+use std::fs;
 use std::path::Path;
 
+/// Parse the tree heights. Every row must be as wide as the first one, since a ragged grid would
+/// make trees at the end of long rows look like they're at the edge
+pub fn parse_grid(input: &str) -> Result<DenseGrid<u32>> {
+    DenseGrid::from_str_with(input, |_, tree_height| {
+        tree_height
+            .to_digit(10)
+            .ok_or_else(|| anyhow!("Invalid character"))
+    })
+}
+
 /// Perform type erasure by boxing the given iterator
 fn box_iter<'a, I: Iterator<Item = T> + 'a, T>(it: I) -> Box<dyn Iterator<Item = T> + 'a> {
     Box::new(it)
 }
 
-fn part_a(trees: &HashMap<(isize, isize), u32>) -> Result<usize> {
-    let width = trees.keys().map(|(x, _)| x + 1).max().unwrap_or(0);
-    let height = trees.keys().map(|(_, y)| y + 1).max().unwrap_or(0);
+fn part_a(trees: &DenseGrid<u32>) -> usize {
+    let width = trees.width() as isize;
+    let height = trees.height() as isize;
 
     // Generate line scans for all directions, for all edge cells
-    let from_left = (0..height).map(|y| box_iter((0..width).map(move |x| (x, y))));
-    let from_right = (0..height).map(|y| box_iter((0..width).rev().map(move |x| (x, y))));
-    let from_top = (0..width).map(|x| box_iter((0..height).map(move |y| (x, y))));
-    let from_bottom = (0..width).map(|x| box_iter((0..height).rev().map(move |y| (x, y))));
+    let from_left = (0..height).map(|y| box_iter((0..width).map(move |x| Coord2::new(x, y))));
+    let from_right =
+        (0..height).map(|y| box_iter((0..width).rev().map(move |x| Coord2::new(x, y))));
+    let from_top = (0..width).map(|x| box_iter((0..height).map(move |y| Coord2::new(x, y))));
+    let from_bottom =
+        (0..width).map(|x| box_iter((0..height).rev().map(move |y| Coord2::new(x, y))));
 
-    let mut visible = HashSet::new();
-    for mut line_scan in from_left
+    // A tree is visible if it's taller than every tree between it and the edge. Since there are
+    // no trees before the edge trees they are always visible
+    let mut visible = vec![false; trees.len()];
+    for line_scan in from_left
         .chain(from_top)
         .chain(from_right)
         .chain(from_bottom)
     {
-        let Some((edge_x, edge_y)) = line_scan.next() else {
-            // This would only happen if there are no trees
-            continue
-        };
-        let Some(mut tallest_tree) = trees.get(&(edge_x, edge_y)) else {
-            return Err(anyhow!("Edge tree is not in set of trees"));
-        };
-        visible.insert((edge_x, edge_y));
-
-        for (x, y) in line_scan {
-            let Some(tree_height) = trees.get(&(x, y)) else {
-                return Err(anyhow!("Tried to access an out of bounds tree"));
+        let mut tallest_tree = None;
+        for pos in line_scan {
+            let (Some(i), Some(&tree_height)) = (trees.index(pos), trees.get(pos)) else {
+                continue;
             };
+            if tallest_tree.is_none_or(|tallest| tree_height > tallest) {
+                tallest_tree = Some(tree_height);
+                visible[i] = true;
+            }
+        }
+    }
+    visible.into_iter().filter(|&v| v).count()
+}
 
-            if tree_height > tallest_tree {
-                tallest_tree = tree_height;
-                visible.insert((x, y));
+/// Explore all four directions from the given tree, compute partial score and multiply them
+/// together
+fn score_tree(trees: &impl Cells<u32>, pos: Coord2) -> usize {
+    let Some(reference_tree) = trees.cell(pos) else {
+        return 0;
+    };
+    let mut score = 1;
+    for (step_x, step_y) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+        let mut partial_score = 0;
+        for neighbor in
+            (1..).map_while(|i| trees.cell(Coord2::new(pos.x + i * step_x, pos.y + i * step_y)))
+        {
+            partial_score += 1;
+            if neighbor >= reference_tree {
+                break;
             }
         }
+        score *= partial_score;
     }
-    Ok(visible.len())
+    score
 }
 
-fn part_b(trees: &HashMap<(isize, isize), u32>) -> usize {
+/// Return the position and score of the tree with the highest scenic score. Ties are broken by
+/// picking the tree with the lowest y and then the lowest x. Works with both the dense and the
+/// sparse grid, so the two can be benchmarked against each other
+pub fn best_scenic(trees: &impl Cells<u32>) -> Option<(Coord2, usize)> {
     trees
-        .iter()
-        .map(|(&(x, y), &reference_tree)| {
-            // Explore all four directions, compute partial score and multiply them together
-            let mut score = 1;
-            for (step_x, step_y) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
-                let mut partial_score = 0;
-                for neighbor in (1..).map_while(|i| trees.get(&(x + i * step_x, y + i * step_y))) {
-                    partial_score += 1;
-                    if *neighbor >= reference_tree {
-                        break;
-                    }
-                }
-                score *= partial_score;
-            }
-            score
-        })
-        .max()
-        .unwrap_or(0)
+        .positions()
+        .map(|pos| (pos, score_tree(trees, pos)))
+        .min_by_key(|&(pos, score)| (Reverse(score), pos.y, pos.x))
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let mut trees = HashMap::new();
-    for (y, lr) in io::BufReader::new(file).lines().enumerate() {
-        for (x, tree_height) in lr?.chars().enumerate() {
-            trees.insert(
-                (x.try_into()?, y.try_into()?),
-                tree_height
-                    .to_digit(10)
-                    .ok_or_else(|| anyhow!("Invalid character"))?,
-            );
-        }
-    }
+fn part_b(trees: &DenseGrid<u32>) -> usize {
+    best_scenic(trees).map(|(_, score)| score).unwrap_or(0)
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let trees = parse_grid(input)?;
 
-    Ok((part_a(&trees)?, Some(part_b(&trees))))
+    Ok((
+        timer.part_a(|| part_a(&trees)),
+        Some(timer.part_b(|| part_b(&trees))),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::grid::Grid;
 
-    fn trees() -> HashMap<(isize, isize), u32> {
-        ["30373", "25512", "65332", "33549", "35390"]
-            .into_iter()
-            .enumerate()
-            .flat_map(|(y, line)| {
-                line.chars()
-                    .enumerate()
-                    .map(move |(x, c)| ((x as isize, y as isize), c.to_digit(10).unwrap()))
-            })
-            .collect()
+    fn trees() -> DenseGrid<u32> {
+        parse_grid("30373\n25512\n65332\n33549\n35390\n").unwrap()
     }
 
     #[test]
-    fn test_example_a() -> Result<()> {
-        assert_eq!(part_a(&trees()).unwrap(), 21);
-        Ok(())
+    fn test_example_a() {
+        assert_eq!(part_a(&trees()), 21);
     }
 
     #[test]
@@ -115,9 +125,60 @@ mod tests {
     }
 
     #[test]
-    fn test_no_trees() -> Result<()> {
-        assert_eq!(part_a(&HashMap::new())?, 0);
-        assert_eq!(part_b(&HashMap::new()), 0);
-        Ok(())
+    fn test_visible_from_single_direction() {
+        // The center tree is only visible from below
+        assert_eq!(part_a(&parse_grid("999\n959\n909").unwrap()), 9);
+
+        // The second tree on the middle row is only visible from the left
+        assert_eq!(part_a(&parse_grid("9999\n1209\n9999").unwrap()), 11);
+    }
+
+    #[test]
+    fn test_hidden_interior() {
+        assert_eq!(part_a(&parse_grid("111\n101\n111").unwrap()), 8);
+        assert_eq!(part_a(&parse_grid("5").unwrap()), 1);
+    }
+
+    #[test]
+    fn test_grid_get() {
+        let trees = trees();
+        assert_eq!(trees.get(Coord2::new(0, 0)), Some(&3));
+        assert_eq!(trees.get(Coord2::new(4, 4)), Some(&0));
+        assert_eq!(trees.get(Coord2::new(2, 3)), Some(&5));
+        assert_eq!(trees.get(Coord2::new(-1, 0)), None);
+        assert_eq!(trees.get(Coord2::new(5, 0)), None);
+        assert_eq!(score_tree(&trees, Coord2::new(2, 3)), 8);
+        assert!(parse_grid("12\n3x").is_err());
+    }
+
+    #[test]
+    fn test_ragged_grid() {
+        let err = parse_grid("123\n45\n678").unwrap_err();
+        assert_eq!(err.to_string(), "Row 1 has length 2, expected 3");
+        assert!(parse_grid("12\n345").is_err());
+        assert!(parse_grid("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_best_scenic() {
+        assert_eq!(best_scenic(&trees()), Some((Coord2::new(2, 3), 8)));
+        assert_eq!(
+            best_scenic(&parse_grid("111\n111\n111").unwrap()),
+            Some((Coord2::new(1, 1), 1))
+        );
+        assert_eq!(best_scenic(&DenseGrid::default()), None);
+
+        // The sparse grid finds the same tree
+        let sparse = trees()
+            .iter()
+            .map(|(pos, &h)| (pos, h))
+            .collect::<Grid<_>>();
+        assert_eq!(best_scenic(&sparse), Some((Coord2::new(2, 3), 8)));
+    }
+
+    #[test]
+    fn test_no_trees() {
+        assert_eq!(part_a(&DenseGrid::default()), 0);
+        assert_eq!(part_b(&DenseGrid::default()), 0);
     }
 }
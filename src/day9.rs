@@ -1,37 +1,22 @@
+use crate::coord::{bounding_box, Coord2, Direction};
+use crate::Timer;
 use anyhow::{anyhow, Result};
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::fs;
 use std::path::Path;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
-struct Coord {
-    x: isize,
-    y: isize,
+/// Move the head the given number of steps in the given direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Move {
+    pub direction: Direction,
+    pub count: isize,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Move {
-    Up(isize),
-    Right(isize),
-    Down(isize),
-    Left(isize),
-}
-
-impl Coord {
-    fn new(x: isize, y: isize) -> Self {
-        Self { x, y }
-    }
-
+impl Coord2 {
     fn iter_moves(self, m: Move) -> impl Iterator<Item = Self> {
-        let (delta, count) = match m {
-            Move::Up(c) => (Coord::new(0, -1), c),
-            Move::Right(c) => (Coord::new(1, 0), c),
-            Move::Down(c) => (Coord::new(0, 1), c),
-            Move::Left(c) => (Coord::new(-1, 0), c),
-        };
-        (1..=count).map(move |step| Coord::new(self.x + step * delta.x, self.y + step * delta.y))
+        let delta = m.direction.delta();
+        (1..=m.count).map(move |step| Coord2::new(self.x + step * delta.x, self.y + step * delta.y))
     }
 }
 
@@ -39,19 +24,34 @@ impl FromStr for Move {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.split_once(' ') {
-            Some(("U", c)) => Ok(Self::Up(c.parse()?)),
-            Some(("R", c)) => Ok(Self::Right(c.parse()?)),
-            Some(("D", c)) => Ok(Self::Down(c.parse()?)),
-            Some(("L", c)) => Ok(Self::Left(c.parse()?)),
-            _ => Err(anyhow!("Invalid move instruction ({})", s)),
+        let Some((direction, count)) = s.split_once(' ') else {
+            return Err(anyhow!("Invalid move instruction ({})", s));
+        };
+        let count = count.parse()?;
+        if count < 1 {
+            return Err(anyhow!("Move must be at least one step ({})", s));
         }
+        let direction = match direction {
+            "U" => Direction::Up,
+            "R" => Direction::Right,
+            "D" => Direction::Down,
+            "L" => Direction::Left,
+            _ => return Err(anyhow!("Invalid move instruction ({})", s)),
+        };
+        Ok(Self { direction, count })
     }
 }
 
-fn num_tail_visits<const N: usize>(moves: &[Move]) -> usize {
-    let mut tail_visited = HashSet::new();
-    let mut rope = [Coord::default(); N];
+/// Parse one move per line
+pub fn parse_moves(input: &str) -> Result<Vec<Move>> {
+    input.lines().map(str::parse).collect()
+}
+
+/// Move a rope with the given number of knots according to the moves. The callback gets the
+/// starting rope and then the rope after every step of the head
+fn simulate(moves: &[Move], knots: usize, mut on_step: impl FnMut(&[Coord2])) {
+    let mut rope = vec![Coord2::default(); knots.max(1)];
+    on_step(&rope);
 
     for move_instruction in moves.iter().copied() {
         for m in rope[0].iter_moves(move_instruction) {
@@ -75,35 +75,107 @@ fn num_tail_visits<const N: usize>(moves: &[Move]) -> usize {
                 }
                 rope[i] = knot;
             }
-            tail_visited.insert(*rope.last().unwrap());
+            on_step(&rope);
         }
     }
-    tail_visited.len()
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let moves = io::BufReader::new(file)
-        .lines()
-        .map(|lr| lr?.parse())
-        .collect::<Result<Vec<Move>>>()?;
+/// Return every position the last knot of a rope with the given number of knots occupies, in
+/// order. The starting position is included and there is one position for every step of the head
+pub fn tail_path(moves: &[Move], knots: usize) -> Vec<Coord2> {
+    let mut path = Vec::new();
+    simulate(moves, knots, |rope| path.push(*rope.last().unwrap()));
+    path
+}
+
+/// Return the number of distinct positions each knot of the rope visits, starting with the head
+pub fn visits_per_knot(moves: &[Move], knots: usize) -> Vec<usize> {
+    let mut visited = vec![HashSet::new(); knots.max(1)];
+    simulate(moves, knots, |rope| {
+        for (knot_visits, &knot) in visited.iter_mut().zip(rope) {
+            knot_visits.insert(knot);
+        }
+    });
+    visited.into_iter().map(|v| v.len()).collect()
+}
+
+fn num_tail_visits<const N: usize>(moves: &[Move]) -> usize {
+    tail_path(moves, N)
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Draw the given rope like the puzzle description does. The head is drawn as `H`, the following
+/// knots as their index and the starting position as `s`. Knots closer to the head are drawn on
+/// top of knots further back
+pub fn render(rope: &[Coord2]) -> String {
+    let start = Coord2::default();
+    let (min, max) = bounding_box(rope.iter().copied().chain([start])).unwrap();
+
+    (min.y..=max.y)
+        .map(|y| {
+            (min.x..=max.x)
+                .map(|x| {
+                    let pos = Coord2::new(x, y);
+                    match rope.iter().position(|&knot| knot == pos) {
+                        Some(0) => 'H',
+                        Some(i) => char::from_digit((i % 10) as u32, 10).unwrap(),
+                        None if pos == start => 's',
+                        None => '.',
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Solve both parts using the puzzle input
+pub fn solve(input: &str, timer: &mut Timer) -> Result<(usize, Option<usize>)> {
+    let moves = parse_moves(input)?;
 
     Ok((
-        num_tail_visits::<2>(&moves),
-        Some(num_tail_visits::<10>(&moves)),
+        timer.part_a(|| num_tail_visits::<2>(&moves)),
+        Some(timer.part_b(|| num_tail_visits::<10>(&moves))),
     ))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&fs::read_to_string(path)?, &mut Timer::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn small_example() -> Vec<Move> {
-        ["R 4", "U 4", "L 3", "D 1", "R 4", "D 1", "L 5", "R 2"]
-            .into_iter()
-            .map(FromStr::from_str)
-            .collect::<Result<Vec<_>>>()
-            .unwrap()
+        parse_moves("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n").unwrap()
+    }
+
+    #[test]
+    fn test_parse_moves() -> Result<()> {
+        let moves = parse_moves("U 3\nL 12\n")?;
+        assert_eq!(
+            moves,
+            [
+                Move {
+                    direction: Direction::Up,
+                    count: 3
+                },
+                Move {
+                    direction: Direction::Left,
+                    count: 12
+                }
+            ]
+        );
+        assert!(parse_moves("")?.is_empty());
+
+        assert!(parse_moves("U 0").is_err());
+        assert!(parse_moves("R 2\nU -3\n").is_err());
+        assert!(parse_moves("X 1").is_err());
+        assert!(parse_moves("U").is_err());
+        Ok(())
     }
 
     #[test]
@@ -116,13 +188,56 @@ mod tests {
         assert_eq!(num_tail_visits::<10>(&small_example()), 1);
     }
 
+    #[test]
+    fn test_tail_path() {
+        // One position for the start and one for each of the 24 steps of the head
+        let path = tail_path(&small_example(), 2);
+        assert_eq!(path.len(), 25);
+        assert_eq!(path.first(), Some(&Coord2::new(0, 0)));
+        assert_eq!(path.last(), Some(&Coord2::new(1, -2)));
+
+        // The tail of a long rope never leaves the start in the small example
+        let path = tail_path(&small_example(), 10);
+        assert_eq!(path.len(), 25);
+        assert!(path.iter().all(|&c| c == Coord2::new(0, 0)));
+    }
+
+    #[test]
+    fn test_render() {
+        let rope = [
+            Coord2::new(4, -4),
+            Coord2::new(4, -3),
+            Coord2::new(4, -2),
+            Coord2::new(3, -2),
+            Coord2::new(2, -2),
+            Coord2::new(1, -1),
+            Coord2::new(0, 0),
+            Coord2::new(0, 0),
+            Coord2::new(0, 0),
+            Coord2::new(0, 0),
+        ];
+        assert_eq!(
+            render(&rope),
+            ["....H", "....1", "..432", ".5...", "6...."].join("\n")
+        );
+        assert_eq!(render(&[Coord2::new(1, 0)]), "sH");
+    }
+
+    fn large_example() -> Vec<Move> {
+        parse_moves("R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20\n").unwrap()
+    }
+
     #[test]
     fn test_example_b_large() {
-        let large_example = ["R 5", "U 8", "L 8", "D 3", "R 17", "D 10", "L 25", "U 20"]
-            .into_iter()
-            .map(FromStr::from_str)
-            .collect::<Result<Vec<Move>>>()
-            .unwrap();
-        assert_eq!(num_tail_visits::<10>(&large_example), 36);
+        assert_eq!(num_tail_visits::<10>(&large_example()), 36);
+    }
+
+    #[test]
+    fn test_visits_per_knot() {
+        let visits = visits_per_knot(&large_example(), 10);
+        assert_eq!(visits.len(), 10);
+        assert_eq!(visits[9], 36);
+
+        assert_eq!(visits_per_knot(&small_example(), 2), [21, 13]);
     }
 }
@@ -0,0 +1,299 @@
+use crate::coord::{self, Coord2};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+
+/// Read access shared by the sparse and the dense grid, for code that works with either
+pub trait Cells<T> {
+    /// Return the value at the given position, or None if the cell is empty or out of bounds
+    fn cell(&self, pos: Coord2) -> Option<&T>;
+
+    /// Iterate over the positions of all non-empty cells
+    fn positions(&self) -> impl Iterator<Item = Coord2> + '_;
+}
+
+/// Sparse 2D grid of values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: HashMap<Coord2, T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Parse a grid where every character of the input is a cell. The parser is given the
+    /// position and character of every cell and may return None to leave the cell empty
+    pub fn from_str_with<F>(s: &str, mut parser: F) -> Result<Self>
+    where
+        F: FnMut(Coord2, char) -> Result<Option<T>>,
+    {
+        let mut grid = Self::new();
+        for (line, y) in s.lines().zip(0..) {
+            for (c, x) in line.chars().zip(0..) {
+                let pos = Coord2::new(x, y);
+                if let Some(value) = parser(pos, c)? {
+                    grid.insert(pos, value);
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn get(&self, pos: Coord2) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    pub fn insert(&mut self, pos: Coord2, value: T) -> Option<T> {
+        self.cells.insert(pos, value)
+    }
+
+    pub fn contains(&self, pos: Coord2) -> bool {
+        self.cells.contains_key(&pos)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Iterate over all non-empty cells in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (Coord2, &T)> {
+        self.cells.iter().map(|(&pos, value)| (pos, value))
+    }
+
+    /// Return the smallest and largest corner of the rectangle that contains every non-empty
+    /// cell, or None if the grid is empty
+    pub fn bounding_box(&self) -> Option<(Coord2, Coord2)> {
+        coord::bounding_box(self.cells.keys().copied())
+    }
+}
+
+impl<T> Default for Grid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<(Coord2, T)> for Grid<T> {
+    fn from_iter<I: IntoIterator<Item = (Coord2, T)>>(iter: I) -> Self {
+        Self {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> Cells<T> for Grid<T> {
+    fn cell(&self, pos: Coord2) -> Option<&T> {
+        self.get(pos)
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Coord2> + '_ {
+        self.cells.keys().copied()
+    }
+}
+
+/// Dense rectangular 2D grid of values stored row by row, with the top left cell at (0, 0).
+/// Lookups are a bounds check and an index instead of a hash, which matters in hot loops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseGrid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> DenseGrid<T> {
+    /// Parse a grid where every character of the input is a cell. Every row must be as wide as
+    /// the first one
+    pub fn from_str_with<F>(s: &str, mut parser: F) -> Result<Self>
+    where
+        F: FnMut(Coord2, char) -> Result<T>,
+    {
+        let mut grid = Self {
+            cells: Vec::new(),
+            width: 0,
+            height: 0,
+        };
+        for (line, y) in s.lines().zip(0..) {
+            let row_start = grid.cells.len();
+            for (c, x) in line.chars().zip(0..) {
+                grid.cells.push(parser(Coord2::new(x, y), c)?);
+            }
+            let len = grid.cells.len() - row_start;
+            if y == 0 {
+                grid.width = len;
+            } else if len != grid.width {
+                return Err(anyhow!(
+                    "Row {} has length {}, expected {}",
+                    y,
+                    len,
+                    grid.width
+                ));
+            }
+            grid.height += 1;
+        }
+        Ok(grid)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Return the index of the given position in the row-major cells, or None if it's out of
+    /// bounds
+    pub fn index(&self, pos: Coord2) -> Option<usize> {
+        let x = usize::try_from(pos.x).ok().filter(|&x| x < self.width)?;
+        let y = usize::try_from(pos.y).ok().filter(|&y| y < self.height)?;
+        Some(y * self.width + x)
+    }
+
+    pub fn get(&self, pos: Coord2) -> Option<&T> {
+        self.index(pos).map(|i| &self.cells[i])
+    }
+
+    /// Iterate over all cells row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Coord2, &T)> {
+        let width = self.width.max(1);
+        self.cells.iter().enumerate().map(move |(i, value)| {
+            let pos = Coord2::new((i % width) as isize, (i / width) as isize);
+            (pos, value)
+        })
+    }
+
+    /// Return the smallest and largest corner of the grid, or None if the grid is empty
+    pub fn bounding_box(&self) -> Option<(Coord2, Coord2)> {
+        if self.is_empty() {
+            return None;
+        }
+        Some((
+            Coord2::new(0, 0),
+            Coord2::new(self.width as isize - 1, self.height as isize - 1),
+        ))
+    }
+}
+
+impl<T> Default for DenseGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: Vec::new(),
+            width: 0,
+            height: 0,
+        }
+    }
+}
+
+impl<T> Cells<T> for DenseGrid<T> {
+    fn cell(&self, pos: Coord2) -> Option<&T> {
+        self.get(pos)
+    }
+
+    fn positions(&self) -> impl Iterator<Item = Coord2> + '_ {
+        self.iter().map(|(pos, _)| pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str_with() -> Result<()> {
+        let grid = Grid::from_str_with("#.\n.##", |_, c| match c {
+            '#' => Ok(Some(())),
+            '.' => Ok(None),
+            _ => Err(anyhow!("Invalid tile {:?}", c)),
+        })?;
+        assert_eq!(grid.len(), 3);
+        assert!(grid.contains(Coord2::new(0, 0)));
+        assert!(!grid.contains(Coord2::new(1, 0)));
+        assert!(grid.contains(Coord2::new(2, 1)));
+        assert!(Grid::from_str_with("#?", |_, c| match c {
+            '#' => Ok(Some(())),
+            _ => Err(anyhow!("Invalid tile {:?}", c)),
+        })
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let mut grid = Grid::new();
+        assert_eq!(grid.bounding_box(), None);
+        grid.insert(Coord2::new(3, -1), 'a');
+        assert_eq!(
+            grid.bounding_box(),
+            Some((Coord2::new(3, -1), Coord2::new(3, -1)))
+        );
+        grid.insert(Coord2::new(-2, 4), 'b');
+        assert_eq!(
+            grid.bounding_box(),
+            Some((Coord2::new(-2, -1), Coord2::new(3, 4)))
+        );
+        assert_eq!(grid.get(Coord2::new(-2, 4)), Some(&'b'));
+    }
+
+    #[test]
+    fn test_dense_from_str_with() -> Result<()> {
+        let grid = DenseGrid::from_str_with("123\n456", |_, c| {
+            c.to_digit(10)
+                .ok_or_else(|| anyhow!("Invalid digit {:?}", c))
+        })?;
+        assert_eq!((grid.width(), grid.height(), grid.len()), (3, 2, 6));
+        assert_eq!(grid.get(Coord2::new(0, 0)), Some(&1));
+        assert_eq!(grid.get(Coord2::new(2, 1)), Some(&6));
+        assert_eq!(grid.get(Coord2::new(3, 0)), None);
+        assert_eq!(grid.get(Coord2::new(0, -1)), None);
+        assert_eq!(
+            grid.iter().map(|(pos, &v)| (pos.x, pos.y, v)).last(),
+            Some((2, 1, 6))
+        );
+        assert_eq!(
+            grid.bounding_box(),
+            Some((Coord2::new(0, 0), Coord2::new(2, 1)))
+        );
+
+        let err = DenseGrid::from_str_with("123\n45\n678", |_, c| Ok(c)).unwrap_err();
+        assert_eq!(err.to_string(), "Row 1 has length 2, expected 3");
+        assert!(DenseGrid::from_str_with("1?", |_, c| {
+            c.to_digit(10)
+                .ok_or_else(|| anyhow!("Invalid digit {:?}", c))
+        })
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dense_matches_sparse() -> Result<()> {
+        let input = "ab\ncd\nef";
+        let dense = DenseGrid::from_str_with(input, |_, c| Ok(c))?;
+        let sparse = Grid::from_str_with(input, |_, c| Ok(Some(c)))?;
+        assert_eq!(dense.bounding_box(), sparse.bounding_box());
+        for pos in sparse.positions() {
+            assert_eq!(dense.cell(pos), sparse.cell(pos));
+        }
+        assert_eq!(dense.positions().count(), sparse.positions().count());
+
+        let empty = DenseGrid::from_str_with("", |_, c| Ok(c))?;
+        assert!(empty.is_empty());
+        assert_eq!(empty.bounding_box(), None);
+        assert_eq!(empty, DenseGrid::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{self, BufRead, Read};
+use std::path::PathBuf;
+
+/// Where to read the puzzle input from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    File(PathBuf),
+    Stdin,
+}
+
+impl From<PathBuf> for InputSource {
+    /// Create an input source from a path, where `-` means stdin
+    fn from(path: PathBuf) -> Self {
+        if path.as_os_str() == "-" {
+            Self::Stdin
+        } else {
+            Self::File(path)
+        }
+    }
+}
+
+/// Read the whole puzzle input into memory
+pub fn read_input(source: InputSource) -> Result<String> {
+    match source {
+        InputSource::File(path) => fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read input from {}", path.display())),
+        InputSource::Stdin => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .context("Failed to read input from stdin")?;
+            Ok(input)
+        }
+    }
+}
+
+/// Iterate over the lines of the reader, skipping blank lines and comment lines starting with `#`.
+/// This is opt-in since both are meaningful for some days, like the blank lines between elves in
+/// day 1. Days 2 and 4 use it
+pub fn data_lines(reader: impl BufRead) -> impl Iterator<Item = Result<String>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) if line.trim().is_empty() || line.trim_start().starts_with('#') => None,
+        Ok(line) => Some(Ok(line)),
+        Err(e) => Some(Err(e.into())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_lines() -> Result<()> {
+        let input = "# Header\nA Y\n\n  \n  # Indented comment\nB X # not a comment\n";
+        assert_eq!(
+            data_lines(input.as_bytes()).collect::<Result<Vec<_>>>()?,
+            ["A Y", "B X # not a comment"]
+        );
+        assert_eq!(data_lines("".as_bytes()).count(), 0);
+        assert!(data_lines(&[0xff, b'\n'][..]).next().unwrap().is_err());
+        Ok(())
+    }
+}
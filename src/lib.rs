@@ -1,3 +1,11 @@
+use anyhow::{anyhow, Result};
+use std::time::{Duration, Instant};
+
+pub mod coord;
+pub mod grid;
+pub mod ranges;
+pub mod search;
+
 pub mod day1;
 pub mod day2;
 pub mod day3;
@@ -24,3 +32,118 @@ pub mod day21;
 pub mod day23;
 pub mod day24;
 pub mod day25;
+
+pub mod input;
+
+/// The answers to both parts of a day's puzzle
+pub trait Day {
+    fn part_a(&self) -> String;
+    fn part_b(&self) -> Option<String>;
+}
+
+impl<A: ToString, B: ToString> Day for (A, Option<B>) {
+    fn part_a(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn part_b(&self) -> Option<String> {
+        self.1.as_ref().map(B::to_string)
+    }
+}
+
+/// How long it took to parse the input and to solve each part of a day's puzzle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part_a: Duration,
+    pub part_b: Duration,
+}
+
+/// Measures each part of a solution separately. Everything that happens before part A starts
+/// counts as parsing
+#[derive(Debug, Clone)]
+pub struct Timer {
+    start: Instant,
+    timings: Timings,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            timings: Timings::default(),
+        }
+    }
+
+    /// Run part A and record how long it took
+    pub fn part_a<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        self.timings.parse = self.start.elapsed();
+        let start = Instant::now();
+        let answer = f();
+        self.timings.part_a = start.elapsed();
+        answer
+    }
+
+    /// Run part B and record how long it took
+    pub fn part_b<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let answer = f();
+        self.timings.part_b = start.elapsed();
+        answer
+    }
+
+    pub fn timings(&self) -> Timings {
+        self.timings
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The days that have a solution
+pub const DAYS: [usize; 24] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 23, 24, 25,
+];
+
+/// Run the solution for the given day using the given puzzle input. Returns the answers along
+/// with how long parsing and each part took
+pub fn run(day: usize, input: &str) -> Result<((String, Option<String>), Timings)> {
+    let mut timer = Timer::new();
+    #[allow(
+        overlapping_range_endpoints,
+        unreachable_patterns,
+        clippy::match_overlapping_arm
+    )]
+    let answers: Box<dyn Day> = match day {
+        1 => Box::new(day1::solve(input, &mut timer)?),
+        2 => Box::new(day2::solve(input, &mut timer)?),
+        3 => Box::new(day3::solve(input, &mut timer)?),
+        4 => Box::new(day4::solve(input, &mut timer)?),
+        5 => Box::new(day5::solve(input, &mut timer)?),
+        6 => Box::new(day6::solve(input, &mut timer)?),
+        7 => Box::new(day7::solve(input, &mut timer)?),
+        8 => Box::new(day8::solve(input, &mut timer)?),
+        9 => Box::new(day9::solve(input, &mut timer)?),
+        10 => Box::new(day10::solve(input, &mut timer)?),
+        11 => Box::new(day11::solve(input, &mut timer)?),
+        12 => Box::new(day12::solve(input, &mut timer)?),
+        13 => Box::new(day13::solve(input, &mut timer)?),
+        14 => Box::new(day14::solve(input, &mut timer)?),
+        15 => Box::new(day15::solve(input, &mut timer)?),
+        16 => Box::new(day16::solve(input, &mut timer)?),
+        17 => Box::new(day17::solve(input, &mut timer)?),
+        18 => Box::new(day18::solve(input, &mut timer)?),
+        19 => Box::new(day19::solve(input, &mut timer)?),
+        20 => Box::new(day20::solve(input, &mut timer)?),
+        21 => Box::new(day21::solve(input, &mut timer)?),
+        23 => Box::new(day23::solve(input, &mut timer)?),
+        24 => Box::new(day24::solve(input, &mut timer)?),
+        25 => Box::new(day25::solve(input, &mut timer)?),
+        1..=25 => return Err(anyhow!("No implementation for this day yet")),
+        day => return Err(anyhow!("Day {} is not a valid day for advent of code", day)),
+    };
+    Ok(((answers.part_a(), answers.part_b()), timer.timings()))
+}
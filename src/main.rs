@@ -1,68 +1,87 @@
+use advent_of_code_2022::input::{read_input, InputSource};
 use anyhow::{anyhow, Result};
 use clap::Parser;
 use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy)]
+enum DaySelection {
+    All,
+    Day(usize),
+}
+
+impl FromStr for DaySelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Self::All),
+            _ => Ok(Self::Day(s.parse().map_err(|_| {
+                anyhow!("Expected a day number or \"all\", got {:?}", s)
+            })?)),
+        }
+    }
+}
 
 #[derive(Debug, Parser)]
 struct Options {
-    /// The day to run the solution for (1-25)
-    day: usize,
+    /// The day to run the solution for (1-25), or "all" to run every solved day
+    #[clap(long)]
+    day: DaySelection,
 
-    /// The input data file. Will look for `data/day<num>.txt` by default
+    /// The input data file, or `-` to read from stdin. Will look for `data/day<num>.txt` by
+    /// default
+    #[clap(long)]
     input: Option<PathBuf>,
+
+    /// Print how long each part took to solve. Parsing the input isn't included in either part
+    #[clap(long)]
+    time: bool,
 }
 
 fn pad_newlines(answer: String) -> String {
     answer.lines().collect::<Vec<_>>().join("\n   ")
 }
 
-fn as_result<A: ToString, B: ToString>((a, b): (A, Option<B>)) -> (String, Option<String>) {
-    (a.to_string(), b.map(|answer| answer.to_string()))
+fn default_input(day: usize) -> PathBuf {
+    format!("data/day{}.txt", day).into()
 }
 
-fn main() -> Result<()> {
-    let opts = Options::parse();
-    let input = opts
-        .input
-        .unwrap_or_else(|| format!("data/day{}.txt", opts.day).into());
-
-    #[allow(
-        overlapping_range_endpoints,
-        unreachable_patterns,
-        clippy::match_overlapping_arm
-    )]
-    let (a, b): (String, Option<String>) = match opts.day {
-        1 => as_result(advent_of_code_2022::day1::main(&input)?),
-        2 => as_result(advent_of_code_2022::day2::main(&input)?),
-        3 => as_result(advent_of_code_2022::day3::main(&input)?),
-        4 => as_result(advent_of_code_2022::day4::main(&input)?),
-        5 => as_result(advent_of_code_2022::day5::main(&input)?),
-        6 => as_result(advent_of_code_2022::day6::main(&input)?),
-        7 => as_result(advent_of_code_2022::day7::main(&input)?),
-        8 => as_result(advent_of_code_2022::day8::main(&input)?),
-        9 => as_result(advent_of_code_2022::day9::main(&input)?),
-        10 => as_result(advent_of_code_2022::day10::main(&input)?),
-        11 => as_result(advent_of_code_2022::day11::main(&input)?),
-        12 => as_result(advent_of_code_2022::day12::main(&input)?),
-        13 => as_result(advent_of_code_2022::day13::main(&input)?),
-        14 => as_result(advent_of_code_2022::day14::main(&input)?),
-        15 => as_result(advent_of_code_2022::day15::main(&input)?),
-        16 => as_result(advent_of_code_2022::day16::main(&input)?),
-        17 => as_result(advent_of_code_2022::day17::main(&input)?),
-        18 => as_result(advent_of_code_2022::day18::main(&input)?),
-        19 => as_result(advent_of_code_2022::day19::main(&input)?),
-        20 => as_result(advent_of_code_2022::day20::main(&input)?),
-        21 => as_result(advent_of_code_2022::day21::main(&input)?),
-        23 => as_result(advent_of_code_2022::day23::main(&input)?),
-        24 => as_result(advent_of_code_2022::day24::main(&input)?),
-        25 => as_result(advent_of_code_2022::day25::main(&input)?),
-        1..=25 => return Err(anyhow!("No implementation for this day yet")),
-        day => return Err(anyhow!("Day {} is not a valid day for advent of code", day)),
-    };
+fn run_day(day: usize, source: InputSource, time: bool) -> Result<()> {
+    let input = read_input(source)?;
+    let ((a, b), timings) = advent_of_code_2022::run(day, &input)?;
 
     println!("A: {}", pad_newlines(a));
     if let Some(b) = b {
         println!("B: {}", pad_newlines(b));
     }
+    if time {
+        println!(
+            "day{}: a={:.1?} b={:.1?}",
+            day, timings.part_a, timings.part_b
+        );
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let opts = Options::parse();
+
+    match opts.day {
+        DaySelection::All => {
+            if opts.input.is_some() {
+                return Err(anyhow!("An input file can't be used when running all days"));
+            }
+            for day in advent_of_code_2022::DAYS {
+                println!("Day {}", day);
+                run_day(day, InputSource::File(default_input(day)), opts.time)?;
+            }
+        }
+        DaySelection::Day(day) => {
+            let input = opts.input.unwrap_or_else(|| default_input(day));
+            run_day(day, InputSource::from(input), opts.time)?;
+        }
+    }
 
     Ok(())
 }
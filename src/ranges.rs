@@ -0,0 +1,40 @@
+use std::cmp::Reverse;
+use std::ops::RangeInclusive;
+
+/// Merge all overlapping ranges in the given vector. The merged ranges are sorted by their start
+pub fn merge<T: Ord + Copy>(mut ranges: Vec<RangeInclusive<T>>) -> Vec<RangeInclusive<T>> {
+    ranges.sort_by_key(|r| Reverse((*r.start(), *r.end())));
+    let mut merged: Vec<RangeInclusive<T>> = Vec::new();
+    while let Some(curr) = ranges.pop() {
+        let Some(prev) = merged.last_mut() else {
+            merged.push(curr);
+            continue;
+        };
+        if curr.start() <= prev.end() {
+            let extended_range = (*prev.start())..=((*curr.end()).max(*prev.end()));
+            *prev = extended_range;
+        } else {
+            merged.push(curr);
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        assert_eq!(merge::<isize>(vec![]), vec![]);
+        assert_eq!(merge(vec![1..=3, 0..=4]), vec![0..=4]);
+        assert_eq!(merge(vec![0..=3, 1..=4]), vec![0..=4]);
+        assert_eq!(merge(vec![0..=5, 1..=4]), vec![0..=5]);
+        assert_eq!(merge(vec![0..=3, 5..=9]), vec![0..=3, 5..=9]);
+        assert_eq!(merge(vec![0..=3, 3..=9]), vec![0..=9]);
+        assert_eq!(
+            merge(vec!['m'..='z', 'a'..='c', 'b'..='d']),
+            vec!['a'..='d', 'm'..='z']
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::{HashSet, VecDeque};
+use std::hash::Hash;
+
+/// Return the number of steps of the shortest path from start to any node that is a goal, or None
+/// if no goal can be reached. Every step is assumed to have the same cost
+pub fn bfs<N, F, I, G>(start: N, mut neighbors: F, mut is_goal: G) -> Option<usize>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+    G: FnMut(&N) -> bool,
+{
+    let mut visited = HashSet::new();
+    visited.insert(start.clone());
+    let mut to_visit = VecDeque::new();
+    to_visit.push_back((0, start));
+
+    while let Some((num_steps, node)) = to_visit.pop_front() {
+        if is_goal(&node) {
+            return Some(num_steps);
+        }
+        for neighbor in neighbors(&node) {
+            if visited.insert(neighbor.clone()) {
+                to_visit.push_back((num_steps + 1, neighbor));
+            }
+        }
+    }
+    None
+}
+
+/// Set of nodes that have been visited by a search. Lets callers with a denser representation
+/// than a HashSet track visited nodes themselves
+pub trait Visited<N> {
+    /// Mark the node as visited. Returns true if it wasn't visited before
+    fn visit(&mut self, node: N) -> bool;
+}
+
+impl<N: Hash + Eq> Visited<N> for HashSet<N> {
+    fn visit(&mut self, node: N) -> bool {
+        self.insert(node)
+    }
+}
+
+/// Return every node that can be reached from start, including start itself
+pub fn flood_fill<N, F, I>(start: N, neighbors: F) -> HashSet<N>
+where
+    N: Hash + Eq + Clone,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    let mut visited = HashSet::new();
+    flood_fill_into(start, &mut visited, neighbors);
+    visited
+}
+
+/// Mark every node that can be reached from start as visited, including start itself. Nodes
+/// that are already visited are treated as walls
+pub fn flood_fill_into<N, V, F, I>(start: N, visited: &mut V, mut neighbors: F)
+where
+    N: Clone,
+    V: Visited<N>,
+    F: FnMut(&N) -> I,
+    I: IntoIterator<Item = N>,
+{
+    if !visited.visit(start.clone()) {
+        return;
+    }
+    let mut to_visit = vec![start];
+
+    while let Some(node) = to_visit.pop() {
+        for neighbor in neighbors(&node) {
+            if visited.visit(neighbor.clone()) {
+                to_visit.push(neighbor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Neighbors on a number line from 0 to 9 where 5 is a wall
+    fn number_line(&n: &i32) -> Vec<i32> {
+        [n - 1, n + 1]
+            .into_iter()
+            .filter(|n| (0..10).contains(n) && *n != 5)
+            .collect()
+    }
+
+    #[test]
+    fn test_bfs() {
+        assert_eq!(bfs(0, number_line, |&n| n == 4), Some(4));
+        assert_eq!(bfs(3, number_line, |&n| n == 3), Some(0));
+        assert_eq!(bfs(3, number_line, |&n| n == 0 || n == 4), Some(1));
+        assert_eq!(bfs(0, number_line, |&n| n == 9), None);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        assert_eq!(flood_fill(2, number_line), (0..5).collect());
+        assert_eq!(flood_fill(7, number_line), (6..10).collect());
+        assert_eq!(flood_fill(5, |_: &i32| []), [5].into_iter().collect());
+    }
+
+    #[test]
+    fn test_flood_fill_into() {
+        // Nodes that are already visited block the fill
+        let mut visited = [3].into_iter().collect::<HashSet<_>>();
+        flood_fill_into(1, &mut visited, number_line);
+        assert_eq!(visited, [0, 1, 2, 3].into_iter().collect());
+
+        flood_fill_into(3, &mut visited, number_line);
+        assert_eq!(visited.len(), 4);
+    }
+}